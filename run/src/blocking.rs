@@ -0,0 +1,32 @@
+//! Blocking wrappers around the async API, for callers without a Tokio
+//! runtime. These must not be called from within an existing runtime.
+
+use crate::outcome::ServerOutcome;
+use crate::{RunMinecraftError, RunOptions};
+use std::path::Path;
+use tokio::io::AsyncWrite;
+use tokio::runtime::Runtime;
+use tokio::sync::mpsc::Receiver;
+
+/// See [`crate::list_server_versions`].
+pub fn list_server_versions(
+  path: &Path,
+  options: &RunOptions,
+) -> Result<Vec<String>, RunMinecraftError> {
+  Runtime::new()?.block_on(crate::list_server_versions(path, options))
+}
+
+/// See [`crate::run_minecraft_server`].
+pub fn run_minecraft_server(
+  path: &Path,
+  options: &RunOptions,
+  commands: Receiver<String>,
+  output_sink: impl AsyncWrite + Unpin,
+) -> Result<ServerOutcome, RunMinecraftError> {
+  Runtime::new()?.block_on(crate::run_minecraft_server(
+    path,
+    options,
+    commands,
+    output_sink,
+  ))
+}
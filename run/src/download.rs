@@ -0,0 +1,128 @@
+use crate::integrity::{self, Artifact, IntegrityError};
+use reqwest::header::RANGE;
+use reqwest::{Client, StatusCode};
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use thiserror::Error;
+use tokio::fs::{self, File, OpenOptions};
+use tokio::io::AsyncWriteExt;
+
+#[derive(Debug, Error)]
+pub enum DownloadError {
+  #[error("error in writing download to disk")]
+  IoError(#[from] std::io::Error),
+  #[error("error in fetching download")]
+  RequestError(#[from] reqwest::Error),
+  #[error("downloaded file is corrupt: {0}")]
+  Corrupt(#[from] IntegrityError),
+}
+
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone, Copy)]
+pub struct DownloadProgress {
+  /// Bytes on disk so far, including any resumed from a previous attempt
+  pub received: u64,
+  /// Size of the complete file, if known
+  pub total: Option<u64>,
+  /// Average transfer rate of the current attempt in bytes per second
+  pub bytes_per_second: f64,
+}
+
+fn partial_path(destination: &Path) -> PathBuf {
+  let mut filename = destination
+    .file_name()
+    .map(OsString::from)
+    .unwrap_or_default();
+  filename.push(".part");
+  destination.with_file_name(filename)
+}
+
+async fn partial_length(path: &Path) -> std::io::Result<u64> {
+  match fs::metadata(path).await {
+    Ok(metadata) => Ok(metadata.len()),
+    Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(0),
+    Err(error) => Err(error),
+  }
+}
+
+#[cfg(unix)]
+async fn sync_directory(path: &Path) -> std::io::Result<()> {
+  File::open(path).await?.sync_all().await
+}
+
+#[cfg(not(unix))]
+async fn sync_directory(_path: &Path) -> std::io::Result<()> {
+  Ok(())
+}
+
+/// Streams `artifact` into `destination`.
+///
+/// Data is written to a `.part` file next to the destination which is resumed
+/// with an HTTP range request if a previous attempt was interrupted. The file
+/// is only renamed into place once it has been verified and synced to disk.
+pub async fn download_file(
+  client: &Client,
+  artifact: &Artifact,
+  destination: &Path,
+  on_progress: impl Fn(DownloadProgress),
+) -> Result<(), DownloadError> {
+  let partial = partial_path(destination);
+  let mut offset = partial_length(&partial).await?;
+
+  let mut request = client.get(&artifact.url);
+  if offset > 0 {
+    request = request.header(RANGE, format!("bytes={offset}-"));
+  }
+  let mut response = request.send().await?;
+  if response.status() == StatusCode::RANGE_NOT_SATISFIABLE {
+    offset = 0;
+    response = client.get(&artifact.url).send().await?;
+  }
+  let mut response = response.error_for_status()?;
+  if response.status() != StatusCode::PARTIAL_CONTENT {
+    offset = 0;
+  }
+
+  let mut file = OpenOptions::new()
+    .create(true)
+    .write(true)
+    .append(offset > 0)
+    .truncate(offset == 0)
+    .open(&partial)
+    .await?;
+  let total = response.content_length().map(|length| offset + length);
+  let started = Instant::now();
+  let mut last_report: Option<Instant> = None;
+  let mut received = offset;
+  while let Some(chunk) = response.chunk().await? {
+    file.write_all(&chunk).await?;
+    received += chunk.len() as u64;
+
+    let now = Instant::now();
+    let finished = Some(received) == total;
+    if finished || last_report.is_none_or(|last| now - last >= PROGRESS_INTERVAL) {
+      last_report = Some(now);
+      on_progress(DownloadProgress {
+        received,
+        total,
+        bytes_per_second: (received - offset) as f64 / (now - started).as_secs_f64(),
+      });
+    }
+  }
+  file.sync_all().await?;
+  drop(file);
+
+  if let Some(error) = integrity::check_file(&partial, artifact)? {
+    fs::remove_file(&partial).await?;
+    return Err(error.into());
+  }
+
+  fs::rename(&partial, destination).await?;
+  if let Some(directory) = destination.parent() {
+    sync_directory(directory).await?;
+  }
+
+  Ok(())
+}
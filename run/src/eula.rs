@@ -0,0 +1,56 @@
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+
+pub const EULA_URL: &str = "https://aka.ms/MinecraftEULA";
+
+const EULA_FILENAME: &str = "eula.txt";
+
+/// Whether `line` is the `eula=...` setting rather than a comment.
+fn is_setting(line: &str) -> bool {
+  line
+    .split_once('=')
+    .is_some_and(|(key, _)| key.trim() == "eula")
+}
+
+/// Whether the Minecraft EULA has been agreed to in the server directory.
+pub fn is_accepted(directory: &Path) -> std::io::Result<bool> {
+  let contents = match fs::read_to_string(directory.join(EULA_FILENAME)) {
+    Ok(contents) => contents,
+    Err(error) if error.kind() == ErrorKind::NotFound => return Ok(false),
+    Err(error) => return Err(error),
+  };
+  // The server reads the setting case-insensitively.
+  Ok(
+    contents
+      .lines()
+      .filter(|line| is_setting(line))
+      .any(|line| {
+        line
+          .split_once('=')
+          .is_some_and(|(_, value)| value.trim().eq_ignore_ascii_case("true"))
+      }),
+  )
+}
+
+/// Records agreement to the Minecraft EULA in the server directory, keeping
+/// anything else already in `eula.txt`.
+pub fn accept(directory: &Path) -> std::io::Result<()> {
+  let path = directory.join(EULA_FILENAME);
+  let contents = match fs::read_to_string(&path) {
+    Ok(contents) => contents,
+    Err(error) if error.kind() == ErrorKind::NotFound => format!(
+      "#By changing the setting below to TRUE you are indicating your agreement to our EULA ({EULA_URL}).\n"
+    ),
+    Err(error) => return Err(error),
+  };
+
+  let mut lines: Vec<&str> = contents
+    .lines()
+    .map(|line| if is_setting(line) { "eula=true" } else { line })
+    .collect();
+  if !lines.iter().any(|line| is_setting(line)) {
+    lines.push("eula=true");
+  }
+  fs::write(path, lines.join("\n") + "\n")
+}
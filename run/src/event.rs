@@ -0,0 +1,214 @@
+use crate::download::DownloadProgress;
+use crate::handle::ServerEvents;
+use crate::java::JavaRuntime;
+use crate::log_parser::GameEvent;
+use crate::OutputMessage;
+use std::path::PathBuf;
+use std::process::ExitStatus;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc::{self, Sender, UnboundedSender};
+
+/// Notification about work the runner is doing on behalf of the caller.
+#[derive(Debug, Clone)]
+pub enum ServerEvent {
+  DownloadProgress(DownloadProgress),
+  /// The Java runtime the server will run on was chosen
+  JavaSelected(JavaRuntime),
+  /// The server process was started
+  Launched {
+    pid: Option<u32>,
+  },
+  /// The server finished starting and is accepting players, `after` it was
+  /// launched
+  Ready {
+    after: Duration,
+  },
+  /// The server wrote a line to its console
+  Output(OutputMessage),
+  /// Lines of output were left out of the event stream because it was not
+  /// read fast enough. Only sent on the stream, since the handler gets every
+  /// line
+  OutputDropped {
+    lines: u64,
+  },
+  /// Something happened in the game, as recognised from the server log
+  Game(GameEvent),
+  /// The server was asked to shut down
+  Stopping,
+  /// The server process exited
+  Exited {
+    status: ExitStatus,
+  },
+  /// The server exited and will be started again after `delay`
+  Restarting {
+    attempt: u32,
+    status: ExitStatus,
+    crash_report: Option<PathBuf>,
+    delay: Duration,
+  },
+}
+
+/// Number of unread lines of output the stream on a
+/// [`ServerHandle`](crate::handle::ServerHandle) holds before leaving new
+/// ones out.
+pub const EVENT_BUFFER: usize = 1024;
+
+pub type EventHandler = Arc<dyn Fn(ServerEvent) + Send + Sync>;
+
+/// Delivers events to the caller's handler and to the stream on their
+/// [`ServerHandle`](crate::handle::ServerHandle).
+pub(crate) struct EventSink {
+  handler: Option<EventHandler>,
+  stream: UnboundedSender<ServerEvent>,
+  /// Lines of output in the stream that have not been read yet
+  queued_output: Arc<AtomicUsize>,
+  /// Lines of output left out of the stream since it was last sent an event
+  dropped_output: AtomicU64,
+  /// Receives every line of output, holding up the server while it is full
+  /// rather than dropping any
+  console: Option<Sender<OutputMessage>>,
+}
+
+impl EventSink {
+  pub(crate) fn new(
+    handler: Option<EventHandler>,
+    console: Option<Sender<OutputMessage>>,
+  ) -> (Self, ServerEvents) {
+    let (stream, receiver) = mpsc::unbounded_channel();
+    let queued_output = Arc::new(AtomicUsize::new(0));
+    let sink = Self {
+      handler,
+      stream,
+      queued_output: queued_output.clone(),
+      dropped_output: AtomicU64::new(0),
+      console,
+    };
+    (sink, ServerEvents::new(receiver, queued_output))
+  }
+
+  pub(crate) fn emit(&self, event: ServerEvent) {
+    if let Some(handler) = &self.handler {
+      handler(event.clone());
+    }
+
+    // Output is by far the busiest kind of event, so only it is limited,
+    // keeping lifecycle events intact for callers who fall behind.
+    if let ServerEvent::Output(_) = event {
+      if self.queued_output.load(Ordering::Acquire) >= EVENT_BUFFER {
+        self.dropped_output.fetch_add(1, Ordering::Relaxed);
+        return;
+      }
+      self.queued_output.fetch_add(1, Ordering::AcqRel);
+    }
+    let lines = self.dropped_output.swap(0, Ordering::Relaxed);
+    if lines > 0 {
+      let _ = self.stream.send(ServerEvent::OutputDropped { lines });
+    }
+    // The stream is allowed to be dropped by callers who are not interested.
+    let _ = self.stream.send(event);
+  }
+
+  /// Emits a line of output, first waiting for room on the console if there
+  /// is one.
+  pub(crate) async fn emit_output(&self, message: OutputMessage) {
+    if let Some(console) = &self.console {
+      // The console is allowed to be closed once the caller stops reading.
+      let _ = console.send(message.clone()).await;
+    }
+    self.emit(ServerEvent::Output(message));
+  }
+}
+
+impl Drop for EventSink {
+  /// Reports lines dropped after the last event, as the stream ends.
+  fn drop(&mut self) {
+    let lines = *self.dropped_output.get_mut();
+    if lines > 0 {
+      let _ = self.stream.send(ServerEvent::OutputDropped { lines });
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::OutputSource;
+  use std::time::SystemTime;
+  use tokio_stream::StreamExt;
+
+  fn output(line: usize) -> ServerEvent {
+    ServerEvent::Output(OutputMessage {
+      source: OutputSource::Stdout,
+      received: SystemTime::now(),
+      line: line.to_string(),
+    })
+  }
+
+  #[tokio::test]
+  async fn drops_only_output_when_behind() {
+    let (sink, mut stream) = EventSink::new(None, None);
+    for line in 0..EVENT_BUFFER + 5 {
+      sink.emit(output(line));
+    }
+    sink.emit(ServerEvent::Stopping);
+    sink.emit(output(0));
+    drop(sink);
+
+    for line in 0..EVENT_BUFFER {
+      let Some(ServerEvent::Output(message)) = stream.next().await else {
+        panic!("Line {line} should be kept");
+      };
+      assert_eq!(message.line, line.to_string());
+    }
+    assert!(matches!(
+      stream.next().await,
+      Some(ServerEvent::OutputDropped { lines: 5 })
+    ));
+    assert!(matches!(stream.next().await, Some(ServerEvent::Stopping)));
+    // The buffer was still full when the last line arrived.
+    assert!(matches!(
+      stream.next().await,
+      Some(ServerEvent::OutputDropped { lines: 1 })
+    ));
+    assert!(stream.next().await.is_none());
+  }
+
+  #[tokio::test]
+  async fn makes_room_as_output_is_read() {
+    let (sink, mut stream) = EventSink::new(None, None);
+    for line in 0..EVENT_BUFFER {
+      sink.emit(output(line));
+    }
+    assert!(matches!(stream.next().await, Some(ServerEvent::Output(_))));
+    sink.emit(output(EVENT_BUFFER));
+    sink.emit(ServerEvent::Stopping);
+    drop(sink);
+
+    let events: Vec<ServerEvent> = stream.collect().await;
+    assert_eq!(events.len(), EVENT_BUFFER + 1);
+    assert!(matches!(events.last(), Some(ServerEvent::Stopping)));
+  }
+
+  #[tokio::test]
+  async fn console_gets_every_line() {
+    let (console, mut lines) = mpsc::channel(1);
+    let (sink, _) = EventSink::new(None, Some(console));
+    let reader = tokio::spawn(async move {
+      let mut count = 0;
+      while lines.recv().await.is_some() {
+        count += 1;
+      }
+      count
+    });
+    for line in 0..EVENT_BUFFER * 2 {
+      let ServerEvent::Output(message) = output(line) else {
+        unreachable!();
+      };
+      sink.emit_output(message).await;
+    }
+    drop(sink);
+    assert_eq!(reader.await.unwrap(), EVENT_BUFFER * 2);
+  }
+}
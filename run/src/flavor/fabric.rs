@@ -0,0 +1,70 @@
+use super::{get_json, select_version, ServerBuild};
+use crate::integrity::Artifact;
+use crate::version::VersionRequest;
+use crate::RunMinecraftError;
+use reqwest::Client;
+use serde::Deserialize;
+
+const META_URL: &str = "https://meta.fabricmc.net/v2/versions";
+
+#[derive(Deserialize)]
+struct GameVersion {
+  version: String,
+}
+
+#[derive(Deserialize)]
+struct ComponentVersion {
+  version: String,
+  stable: bool,
+}
+
+#[derive(Deserialize)]
+struct LoaderEntry {
+  loader: ComponentVersion,
+}
+
+/// Picks the newest stable entry, or the newest entry if none are stable.
+fn newest_stable<'a>(
+  versions: impl Clone + Iterator<Item = &'a ComponentVersion>,
+) -> Option<&'a ComponentVersion> {
+  versions
+    .clone()
+    .find(|version| version.stable)
+    .or_else(|| versions.into_iter().next())
+}
+
+pub async fn versions(client: &Client) -> Result<Vec<String>, RunMinecraftError> {
+  let versions: Vec<GameVersion> = get_json(client, &format!("{META_URL}/game")).await?;
+  Ok(versions.into_iter().map(|entry| entry.version).collect())
+}
+
+pub async fn resolve(
+  client: &Client,
+  request: &VersionRequest,
+) -> Result<ServerBuild, RunMinecraftError> {
+  let version = select_version(&versions(client).await?, request)?;
+  let loaders: Vec<LoaderEntry> = get_json(client, &format!("{META_URL}/loader/{version}")).await?;
+  let loader = newest_stable(loaders.iter().map(|entry| &entry.loader))
+    .ok_or(RunMinecraftError::CouldNotFindServer)?;
+  let installers: Vec<ComponentVersion> =
+    get_json(client, &format!("{META_URL}/installer")).await?;
+  let installer = newest_stable(installers.iter()).ok_or(RunMinecraftError::CouldNotFindServer)?;
+
+  Ok(ServerBuild {
+    filename: format!(
+      "fabric-server-mc.{version}-loader.{}-launcher.{}.jar",
+      loader.version, installer.version
+    ),
+    artifact: Artifact {
+      url: format!(
+        "{META_URL}/loader/{version}/{}/{}/server/jar",
+        loader.version, installer.version
+      ),
+      size: None,
+      checksum: None,
+    },
+    build: Some(loader.version.clone()),
+    version,
+    java_version: None,
+  })
+}
@@ -0,0 +1,155 @@
+use super::{compare_versions, get_json, select_version, Launch, ServerBuild};
+use crate::integrity::{Artifact, Checksum};
+use crate::version::VersionRequest;
+use crate::RunMinecraftError;
+use reqwest::Client;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::Path;
+use std::process::Stdio;
+use tokio::process::Command;
+
+const PROMOTIONS_URL: &str =
+  "https://files.minecraftforge.net/net/minecraftforge/forge/promotions_slim.json";
+const MAVEN_URL: &str = "https://maven.minecraftforge.net/net/minecraftforge/forge";
+
+#[cfg(windows)]
+const ARGS_FILENAME: &str = "win_args.txt";
+#[cfg(not(windows))]
+const ARGS_FILENAME: &str = "unix_args.txt";
+
+#[derive(Deserialize)]
+struct Promotions {
+  promos: HashMap<String, String>,
+}
+
+async fn promotions(client: &Client) -> Result<HashMap<String, String>, RunMinecraftError> {
+  let promotions: Promotions = get_json(client, PROMOTIONS_URL).await?;
+  Ok(promotions.promos)
+}
+
+fn game_versions(promotions: &HashMap<String, String>) -> Vec<String> {
+  let mut versions: Vec<String> = promotions
+    .keys()
+    .filter_map(|key| {
+      key
+        .strip_suffix("-recommended")
+        .or_else(|| key.strip_suffix("-latest"))
+    })
+    .map(String::from)
+    .collect();
+  versions.sort_by(|left, right| compare_versions(right, left));
+  versions.dedup();
+  versions
+}
+
+pub async fn versions(client: &Client) -> Result<Vec<String>, RunMinecraftError> {
+  Ok(game_versions(&promotions(client).await?))
+}
+
+pub async fn resolve(
+  client: &Client,
+  request: &VersionRequest,
+) -> Result<ServerBuild, RunMinecraftError> {
+  let promotions = promotions(client).await?;
+  let version = select_version(&game_versions(&promotions), request)?;
+  let forge_version = promotions
+    .get(&format!("{version}-recommended"))
+    .or_else(|| promotions.get(&format!("{version}-latest")))
+    .ok_or(RunMinecraftError::CouldNotFindServer)?;
+  let full_version = format!("{version}-{forge_version}");
+  let filename = format!("forge-{full_version}-installer.jar");
+  let url = format!("{MAVEN_URL}/{full_version}/{filename}");
+
+  let checksum = match client
+    .get(format!("{url}.sha1"))
+    .send()
+    .await
+    .and_then(|response| response.error_for_status())
+  {
+    Ok(response) => Some(Checksum::Sha1(response.text().await?.trim().to_string())),
+    Err(_) => None,
+  };
+
+  Ok(ServerBuild {
+    build: Some(forge_version.clone()),
+    filename,
+    artifact: Artifact {
+      url,
+      size: None,
+      checksum,
+    },
+    version,
+    java_version: None,
+  })
+}
+
+/// Version string the installer names its files after, such as
+/// `1.20.1-47.1.0`.
+fn full_version(build: &ServerBuild) -> String {
+  format!(
+    "{}-{}",
+    build.version,
+    build.build.as_deref().unwrap_or_default()
+  )
+}
+
+fn args_file(full_version: &str) -> String {
+  format!("libraries/net/minecraftforge/forge/{full_version}/{ARGS_FILENAME}")
+}
+
+/// Locates an installed Forge server, which is either started through an
+/// argument file (1.17 onwards) or a standalone jar (older versions).
+fn find_launch(directory: &Path, full_version: &str) -> Option<Launch> {
+  let args_file = args_file(full_version);
+  if directory.join(&args_file).is_file() {
+    return Some(Launch::ArgsFile(args_file));
+  }
+
+  [
+    format!("forge-{full_version}.jar"),
+    format!("forge-{full_version}-universal.jar"),
+  ]
+  .into_iter()
+  .find(|jar| directory.join(jar).is_file())
+  .map(Launch::Jar)
+}
+
+/// How the server will be launched once installed, without running the
+/// installer.
+pub fn planned_launch(directory: &Path, build: &ServerBuild) -> Launch {
+  let full_version = full_version(build);
+  find_launch(directory, &full_version).unwrap_or_else(|| {
+    // Installers for 1.17 and later, the first versions to need Java 16,
+    // write an argument file instead of a server jar.
+    if build.java_version.is_some_and(|java| java >= 16) {
+      Launch::ArgsFile(args_file(&full_version))
+    } else {
+      Launch::Jar(format!("forge-{full_version}.jar"))
+    }
+  })
+}
+
+pub async fn install(
+  directory: &Path,
+  build: &ServerBuild,
+  java: &Path,
+) -> Result<Launch, RunMinecraftError> {
+  let full_version = full_version(build);
+  if let Some(launch) = find_launch(directory, &full_version) {
+    return Ok(launch);
+  }
+
+  let status = Command::new(java)
+    .current_dir(directory)
+    .args(["-jar", &build.filename, "--installServer"])
+    .stdout(Stdio::null())
+    .stderr(Stdio::null())
+    .status()
+    .await?;
+  if !status.success() {
+    return Err(RunMinecraftError::InstallFailed(status));
+  }
+
+  find_launch(directory, &full_version).ok_or(RunMinecraftError::CouldNotFindServer)
+}
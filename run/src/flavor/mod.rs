@@ -0,0 +1,173 @@
+mod fabric;
+mod forge;
+mod paper;
+mod purpur;
+mod vanilla;
+
+use crate::integrity::Artifact;
+use crate::version::VersionRequest;
+use crate::RunMinecraftError;
+use clap::ValueEnum;
+use reqwest::Client;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::path::Path;
+
+/// Distribution of the Minecraft server to run.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Flavor {
+  #[default]
+  Vanilla,
+  Paper,
+  Purpur,
+  Fabric,
+  Forge,
+}
+
+/// A specific build of a server distribution, ready to be downloaded.
+#[derive(Debug, Clone)]
+pub struct ServerBuild {
+  /// Minecraft version the build runs
+  pub version: String,
+  /// Distribution-specific build or loader version, if any
+  pub build: Option<String>,
+  /// Name of the downloaded file inside the server directory
+  pub filename: String,
+  pub artifact: Artifact,
+  /// Major version of Java the build needs, if known
+  pub java_version: Option<u32>,
+}
+
+/// How to hand an installed server to the JVM.
+#[derive(Debug, Clone)]
+pub enum Launch {
+  /// An executable jar, passed with `-jar`
+  Jar(String),
+  /// A JVM argument file, passed with `@`
+  ArgsFile(String),
+}
+
+impl Launch {
+  pub fn java_args(&self) -> Vec<String> {
+    match self {
+      Self::Jar(jar) => vec!["-jar".to_string(), jar.clone()],
+      Self::ArgsFile(file) => vec![format!("@{file}")],
+    }
+  }
+}
+
+impl Flavor {
+  /// Lists the Minecraft versions this distribution offers, newest first.
+  pub async fn versions(
+    self,
+    client: &Client,
+    manifest_url: &str,
+  ) -> Result<Vec<String>, RunMinecraftError> {
+    match self {
+      Self::Vanilla => vanilla::versions(client, manifest_url).await,
+      Self::Paper => paper::versions(client).await,
+      Self::Purpur => purpur::versions(client).await,
+      Self::Fabric => fabric::versions(client).await,
+      Self::Forge => forge::versions(client).await,
+    }
+  }
+
+  pub async fn resolve(
+    self,
+    client: &Client,
+    manifest_url: &str,
+    request: &VersionRequest,
+  ) -> Result<ServerBuild, RunMinecraftError> {
+    let mut build = match self {
+      Self::Vanilla => vanilla::resolve(client, manifest_url, request).await,
+      Self::Paper => paper::resolve(client, request).await,
+      Self::Purpur => purpur::resolve(client, request).await,
+      Self::Fabric => fabric::resolve(client, request).await,
+      Self::Forge => forge::resolve(client, request).await,
+    }?;
+    // Other distributions run on the vanilla server, so they need the same
+    // Java version it does.
+    if build.java_version.is_none() && self != Self::Vanilla {
+      build.java_version = vanilla::java_version(client, manifest_url, &build.version).await?;
+    }
+    Ok(build)
+  }
+
+  /// How the server will be launched once installed, without changing the
+  /// server directory.
+  pub fn planned_launch(self, directory: &Path, build: &ServerBuild) -> Launch {
+    match self {
+      Self::Forge => forge::planned_launch(directory, build),
+      _ => Launch::Jar(build.filename.clone()),
+    }
+  }
+
+  /// Performs any setup needed after `build` has been downloaded into
+  /// `directory`, using the `java` executable if an installer has to be run,
+  /// and returns how to launch it.
+  pub async fn install(
+    self,
+    directory: &Path,
+    build: &ServerBuild,
+    java: &Path,
+  ) -> Result<Launch, RunMinecraftError> {
+    match self {
+      Self::Forge => forge::install(directory, build, java).await,
+      _ => Ok(Launch::Jar(build.filename.clone())),
+    }
+  }
+}
+
+async fn get_json<T: DeserializeOwned>(client: &Client, url: &str) -> Result<T, reqwest::Error> {
+  client
+    .get(url)
+    .send()
+    .await?
+    .error_for_status()?
+    .json()
+    .await
+}
+
+/// Whether `version` names a full release rather than a snapshot or
+/// pre-release.
+fn is_release(version: &str) -> bool {
+  version
+    .split('.')
+    .all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit()))
+}
+
+fn compare_versions(left: &str, right: &str) -> Ordering {
+  let parts = |version: &str| -> Vec<u32> {
+    version
+      .split(|c: char| !c.is_ascii_digit())
+      .map(|part| part.parse().unwrap_or(0))
+      .collect()
+  };
+  parts(left).cmp(&parts(right))
+}
+
+/// Picks the version satisfying `request` from `versions`, which must be
+/// ordered newest first.
+fn select_version(
+  versions: &[String],
+  request: &VersionRequest,
+) -> Result<String, RunMinecraftError> {
+  match request {
+    VersionRequest::Pinned(id) => versions
+      .iter()
+      .find(|version| *version == id)
+      .cloned()
+      .ok_or_else(|| RunMinecraftError::UnknownVersion(id.clone())),
+    VersionRequest::LatestRelease => versions
+      .iter()
+      .find(|version| is_release(version))
+      .cloned()
+      .ok_or(RunMinecraftError::CouldNotFindServer),
+    VersionRequest::LatestSnapshot => versions
+      .first()
+      .cloned()
+      .ok_or(RunMinecraftError::CouldNotFindServer),
+  }
+}
@@ -0,0 +1,73 @@
+use super::{get_json, select_version, ServerBuild};
+use crate::integrity::{Artifact, Checksum};
+use crate::version::VersionRequest;
+use crate::RunMinecraftError;
+use reqwest::Client;
+use serde::Deserialize;
+
+const PROJECT_URL: &str = "https://api.papermc.io/v2/projects/paper";
+
+#[derive(Deserialize)]
+struct Project {
+  versions: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct Builds {
+  builds: Vec<Build>,
+}
+
+#[derive(Deserialize)]
+struct Build {
+  build: u32,
+  channel: String,
+  downloads: Downloads,
+}
+
+#[derive(Deserialize)]
+struct Downloads {
+  application: Application,
+}
+
+#[derive(Deserialize)]
+struct Application {
+  name: String,
+  sha256: String,
+}
+
+pub async fn versions(client: &Client) -> Result<Vec<String>, RunMinecraftError> {
+  let project: Project = get_json(client, PROJECT_URL).await?;
+  Ok(project.versions.into_iter().rev().collect())
+}
+
+pub async fn resolve(
+  client: &Client,
+  request: &VersionRequest,
+) -> Result<ServerBuild, RunMinecraftError> {
+  let version = select_version(&versions(client).await?, request)?;
+  let builds: Builds =
+    get_json(client, &format!("{PROJECT_URL}/versions/{version}/builds")).await?;
+  let build = builds
+    .builds
+    .iter()
+    .rev()
+    .find(|build| build.channel == "default")
+    .or(builds.builds.last())
+    .ok_or(RunMinecraftError::CouldNotFindServer)?;
+  let application = &build.downloads.application;
+
+  Ok(ServerBuild {
+    build: Some(build.build.to_string()),
+    filename: application.name.clone(),
+    artifact: Artifact {
+      url: format!(
+        "{PROJECT_URL}/versions/{version}/builds/{}/downloads/{}",
+        build.build, application.name
+      ),
+      size: None,
+      checksum: Some(Checksum::Sha256(application.sha256.clone())),
+    },
+    version,
+    java_version: None,
+  })
+}
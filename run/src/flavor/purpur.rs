@@ -0,0 +1,44 @@
+use super::{get_json, select_version, ServerBuild};
+use crate::integrity::{Artifact, Checksum};
+use crate::version::VersionRequest;
+use crate::RunMinecraftError;
+use reqwest::Client;
+use serde::Deserialize;
+
+const PROJECT_URL: &str = "https://api.purpurmc.org/v2/purpur";
+
+#[derive(Deserialize)]
+struct Project {
+  versions: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct Build {
+  build: String,
+  md5: String,
+}
+
+pub async fn versions(client: &Client) -> Result<Vec<String>, RunMinecraftError> {
+  let project: Project = get_json(client, PROJECT_URL).await?;
+  Ok(project.versions.into_iter().rev().collect())
+}
+
+pub async fn resolve(
+  client: &Client,
+  request: &VersionRequest,
+) -> Result<ServerBuild, RunMinecraftError> {
+  let version = select_version(&versions(client).await?, request)?;
+  let build: Build = get_json(client, &format!("{PROJECT_URL}/{version}/latest")).await?;
+
+  Ok(ServerBuild {
+    filename: format!("purpur-{version}-{}.jar", build.build),
+    artifact: Artifact {
+      url: format!("{PROJECT_URL}/{version}/{}/download", build.build),
+      size: None,
+      checksum: Some(Checksum::Md5(build.md5)),
+    },
+    build: Some(build.build),
+    version,
+    java_version: None,
+  })
+}
@@ -0,0 +1,64 @@
+use super::ServerBuild;
+use crate::integrity::Artifact;
+use crate::version::{VersionRequest, VersionResolver};
+use crate::RunMinecraftError;
+use reqwest::Client;
+
+pub async fn versions(
+  client: &Client,
+  manifest_url: &str,
+) -> Result<Vec<String>, RunMinecraftError> {
+  let manifest = VersionResolver::new(client.clone(), manifest_url)
+    .manifest()
+    .await?;
+  Ok(
+    manifest
+      .versions
+      .into_iter()
+      .map(|entry| entry.id)
+      .collect(),
+  )
+}
+
+pub async fn resolve(
+  client: &Client,
+  manifest_url: &str,
+  request: &VersionRequest,
+) -> Result<ServerBuild, RunMinecraftError> {
+  let resolver = VersionResolver::new(client.clone(), manifest_url);
+  let manifest = resolver.manifest().await?;
+  let entry = manifest.select(request).ok_or_else(|| match request {
+    VersionRequest::Pinned(id) => RunMinecraftError::UnknownVersion(id.clone()),
+    _ => RunMinecraftError::CouldNotFindServer,
+  })?;
+  let details = resolver.details(entry).await?;
+  let server_download = details
+    .downloads
+    .server
+    .as_ref()
+    .ok_or(RunMinecraftError::CouldNotFindServer)?;
+
+  Ok(ServerBuild {
+    filename: format!("minecraft_server.{}.jar", details.id),
+    artifact: Artifact::from(server_download),
+    java_version: details.java_version.map(|java| java.major_version),
+    version: details.id,
+    build: None,
+  })
+}
+
+/// Looks up the Java version Mojang requires for Minecraft `version`, if it is
+/// listed in the manifest.
+pub async fn java_version(
+  client: &Client,
+  manifest_url: &str,
+  version: &str,
+) -> Result<Option<u32>, RunMinecraftError> {
+  let resolver = VersionResolver::new(client.clone(), manifest_url);
+  let manifest = resolver.manifest().await?;
+  let Some(entry) = manifest.find(version) else {
+    return Ok(None);
+  };
+  let details = resolver.details(entry).await?;
+  Ok(details.java_version.map(|java| java.major_version))
+}
@@ -0,0 +1,102 @@
+use crate::event::ServerEvent;
+use crate::outcome::ServerOutcome;
+use crate::shutdown::{self, ShutdownRequest};
+use crate::RunMinecraftError;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tokio::sync::mpsc::error::SendError;
+use tokio::sync::mpsc::{Sender, UnboundedReceiver};
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tokio_stream::Stream;
+
+/// Stream of everything that happens to a server, ending once it has exited
+/// for good.
+pub struct ServerEvents {
+  receiver: UnboundedReceiver<ServerEvent>,
+  queued_output: Arc<AtomicUsize>,
+}
+
+impl ServerEvents {
+  pub(crate) fn new(
+    receiver: UnboundedReceiver<ServerEvent>,
+    queued_output: Arc<AtomicUsize>,
+  ) -> Self {
+    Self {
+      receiver,
+      queued_output,
+    }
+  }
+}
+
+impl Stream for ServerEvents {
+  type Item = ServerEvent;
+
+  fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+    let event = self.receiver.poll_recv(cx);
+    if let Poll::Ready(Some(ServerEvent::Output(_))) = event {
+      self.queued_output.fetch_sub(1, Ordering::AcqRel);
+    }
+    event
+  }
+}
+
+/// A running Minecraft server, started with
+/// [`start_minecraft_server`](crate::start_minecraft_server).
+pub struct ServerHandle {
+  commands: Sender<String>,
+  events: Option<ServerEvents>,
+  request_shutdown: Arc<watch::Sender<ShutdownRequest>>,
+  task: JoinHandle<Result<ServerOutcome, RunMinecraftError>>,
+}
+
+impl ServerHandle {
+  pub(crate) fn new(
+    commands: Sender<String>,
+    events: ServerEvents,
+    request_shutdown: Arc<watch::Sender<ShutdownRequest>>,
+    task: JoinHandle<Result<ServerOutcome, RunMinecraftError>>,
+  ) -> Self {
+    Self {
+      commands,
+      events: Some(events),
+      request_shutdown,
+      task,
+    }
+  }
+
+  /// Sender for lines to type into the server console.
+  pub fn commands(&self) -> Sender<String> {
+    self.commands.clone()
+  }
+
+  pub async fn send_command(&self, command: impl Into<String>) -> Result<(), SendError<String>> {
+    self.commands.send(command.into()).await
+  }
+
+  /// Takes the stream of server events, which can only be taken once.
+  ///
+  /// Events are buffered from the moment the server is started. Lifecycle
+  /// and game events are always kept, but at most
+  /// [`EVENT_BUFFER`](crate::event::EVENT_BUFFER) unread lines of output are,
+  /// and lines beyond that are replaced by a
+  /// [`ServerEvent::OutputDropped`] noting how many were left out. The
+  /// [`on_event`](crate::RunOptions::on_event) handler still gets every line,
+  /// so callers who only use the handler need not take the stream at all.
+  pub fn take_events(&mut self) -> Option<ServerEvents> {
+    self.events.take()
+  }
+
+  /// Waits for the server to exit for good.
+  pub async fn wait(self) -> Result<ServerOutcome, RunMinecraftError> {
+    self.task.await?
+  }
+
+  /// Stops the server gracefully and waits for it to exit.
+  pub async fn stop(self) -> Result<ServerOutcome, RunMinecraftError> {
+    shutdown::request(&self.request_shutdown, ShutdownRequest::Graceful);
+    self.wait().await
+  }
+}
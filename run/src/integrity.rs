@@ -0,0 +1,106 @@
+use md5::Md5;
+use sha1::{Digest, Sha1};
+use sha2::Sha256;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use thiserror::Error;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Checksum {
+  Sha1(String),
+  Sha256(String),
+  Md5(String),
+}
+
+impl Checksum {
+  fn algorithm(&self) -> &'static str {
+    match self {
+      Self::Sha1(_) => "SHA-1",
+      Self::Sha256(_) => "SHA-256",
+      Self::Md5(_) => "MD5",
+    }
+  }
+
+  fn expected(&self) -> &str {
+    match self {
+      Self::Sha1(hash) | Self::Sha256(hash) | Self::Md5(hash) => hash,
+    }
+  }
+
+  fn compute(&self, path: &Path) -> std::io::Result<String> {
+    match self {
+      Self::Sha1(_) => hash_file::<Sha1>(path),
+      Self::Sha256(_) => hash_file::<Sha256>(path),
+      Self::Md5(_) => hash_file::<Md5>(path),
+    }
+  }
+}
+
+/// A file published for download along with whatever integrity information
+/// its publisher provides.
+#[derive(Debug, Clone)]
+pub struct Artifact {
+  pub url: String,
+  pub size: Option<u64>,
+  pub checksum: Option<Checksum>,
+}
+
+#[derive(Debug, Error)]
+pub enum IntegrityError {
+  #[error("expected {expected} bytes but found {actual}")]
+  SizeMismatch { expected: u64, actual: u64 },
+  #[error("expected {algorithm} {expected} but found {actual}")]
+  ChecksumMismatch {
+    algorithm: &'static str,
+    expected: String,
+    actual: String,
+  },
+}
+
+fn hash_file<D: Digest>(path: &Path) -> std::io::Result<String> {
+  let mut file = File::open(path)?;
+  let mut hasher = D::new();
+  let mut buffer = [0; 64 * 1024];
+  loop {
+    let read = file.read(&mut buffer)?;
+    if read == 0 {
+      break;
+    }
+    hasher.update(&buffer[..read]);
+  }
+  Ok(
+    hasher
+      .finalize()
+      .iter()
+      .map(|byte| format!("{byte:02x}"))
+      .collect(),
+  )
+}
+
+/// Checks the file at `path` against the size and checksum published for
+/// `artifact`, returning the first mismatch found.
+pub fn check_file(path: &Path, artifact: &Artifact) -> std::io::Result<Option<IntegrityError>> {
+  let size = path.metadata()?.len();
+  if let Some(expected) = artifact.size {
+    if size != expected {
+      return Ok(Some(IntegrityError::SizeMismatch {
+        expected,
+        actual: size,
+      }));
+    }
+  }
+
+  if let Some(checksum) = &artifact.checksum {
+    let actual = checksum.compute(path)?;
+    if !actual.eq_ignore_ascii_case(checksum.expected()) {
+      return Ok(Some(IntegrityError::ChecksumMismatch {
+        algorithm: checksum.algorithm(),
+        expected: checksum.expected().to_string(),
+        actual,
+      }));
+    }
+  }
+
+  Ok(None)
+}
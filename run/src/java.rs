@@ -0,0 +1,301 @@
+use std::collections::HashSet;
+use std::fmt::{self, Display, Formatter};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::{env, fs};
+use thiserror::Error;
+use tokio::process::Command;
+
+/// Directories that commonly hold one Java installation per subdirectory.
+#[cfg(unix)]
+const INSTALL_DIRECTORIES: &[&str] = &[
+  "/usr/lib/jvm",
+  "/usr/lib64/jvm",
+  "/usr/java",
+  "/opt/java",
+  "/opt/jdk",
+  "/Library/Java/JavaVirtualMachines",
+];
+#[cfg(not(unix))]
+const INSTALL_DIRECTORIES: &[&str] = &[
+  "C:\\Program Files\\Java",
+  "C:\\Program Files\\Eclipse Adoptium",
+  "C:\\Program Files\\Microsoft",
+  "C:\\Program Files\\Zulu",
+];
+
+/// Install directories relative to the home directory.
+const HOME_INSTALL_DIRECTORIES: &[&str] = &[".sdkman/candidates/java", ".jdks"];
+
+#[derive(Debug, Error)]
+pub enum JavaError {
+  #[error("could not find a Java runtime, install one or set JAVA_HOME")]
+  NotFound,
+  #[error("could not determine the version of Java at {}", .0.display())]
+  UnknownVersion(PathBuf),
+  #[error(
+    "the server needs Java {required} or newer, but {} is Java {}",
+    .runtime.executable.display(),
+    .runtime.major_version
+  )]
+  Unsuitable { runtime: JavaRuntime, required: u32 },
+  #[error(
+    "the server needs Java {required} or newer, but only found Java {}",
+    .found.iter().map(u32::to_string).collect::<Vec<_>>().join(", ")
+  )]
+  NoSuitableRuntime { required: u32, found: Vec<u32> },
+}
+
+/// An installed Java runtime.
+#[derive(Debug, Clone)]
+pub struct JavaRuntime {
+  pub executable: PathBuf,
+  /// Version as reported by `java -version`, e.g. `17.0.8` or `1.8.0_382`
+  pub version: String,
+  pub major_version: u32,
+}
+
+impl Display for JavaRuntime {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    write!(f, "Java {} ({})", self.version, self.executable.display())
+  }
+}
+
+impl JavaRuntime {
+  /// Runs `executable -version` to find out which version of Java it is.
+  pub async fn probe(executable: &Path) -> Option<Self> {
+    let output = Command::new(executable)
+      .arg("-version")
+      .stdin(Stdio::null())
+      .output()
+      .await
+      .ok()?;
+    // The version goes to stderr, but some distributions print it to stdout.
+    let version = [&output.stderr, &output.stdout]
+      .into_iter()
+      .find_map(|output| parse_version_output(&String::from_utf8_lossy(output)))?;
+
+    Some(Self {
+      executable: executable.to_path_buf(),
+      major_version: major_version(&version)?,
+      version,
+    })
+  }
+}
+
+/// Finds the version string in the output of `java -version`, such as
+/// `openjdk version "17.0.8" 2023-07-18`.
+fn parse_version_output(output: &str) -> Option<String> {
+  output.lines().find_map(|line| {
+    let (_, rest) = line.split_once(" version \"")?;
+    let (version, _) = rest.split_once('"')?;
+    Some(version.to_string())
+  })
+}
+
+/// Gets the major version from a Java version string, which is the second
+/// component for Java 8 and older (`1.8.0_382`) and the first afterwards.
+fn major_version(version: &str) -> Option<u32> {
+  let mut parts = version
+    .split(|c: char| !c.is_ascii_digit())
+    .map(|part| part.parse::<u32>());
+  match parts.next()?.ok()? {
+    1 => parts.next()?.ok(),
+    major => Some(major),
+  }
+}
+
+fn java_in(home: &Path) -> PathBuf {
+  home
+    .join("bin")
+    .join(format!("java{}", env::consts::EXE_SUFFIX))
+}
+
+/// Lists Java executables that may exist, most preferred first: the one in
+/// `JAVA_HOME`, those on the `PATH`, then those in common install locations.
+fn candidates() -> Vec<PathBuf> {
+  let mut candidates = Vec::new();
+  if let Some(java_home) = env::var_os("JAVA_HOME").filter(|home| !home.is_empty()) {
+    candidates.push(java_in(Path::new(&java_home)));
+  }
+  if let Some(path) = env::var_os("PATH") {
+    candidates.extend(
+      env::split_paths(&path)
+        .map(|directory| directory.join(format!("java{}", env::consts::EXE_SUFFIX))),
+    );
+  }
+
+  let home = env::var_os("HOME").or_else(|| env::var_os("USERPROFILE"));
+  let install_directories =
+    INSTALL_DIRECTORIES
+      .iter()
+      .map(PathBuf::from)
+      .chain(home.iter().flat_map(|home| {
+        HOME_INSTALL_DIRECTORIES
+          .iter()
+          .map(|dir| Path::new(home).join(dir))
+      }));
+  for directory in install_directories {
+    let Ok(entries) = fs::read_dir(&directory) else {
+      continue;
+    };
+    let mut installations: Vec<PathBuf> = entries
+      .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+      .collect();
+    installations.sort();
+    for installation in installations {
+      // macOS bundles keep the runtime under `Contents/Home`.
+      candidates.push(java_in(&installation));
+      candidates.push(java_in(&installation.join("Contents").join("Home")));
+    }
+  }
+
+  candidates
+}
+
+/// Finds the Java runtimes installed on this machine, most preferred first.
+pub async fn find_runtimes() -> Vec<JavaRuntime> {
+  let mut seen = HashSet::new();
+  let mut runtimes = Vec::new();
+  for candidate in candidates() {
+    if !candidate.is_file() {
+      continue;
+    }
+    // The same runtime is often reachable through several symlinks.
+    let canonical = fs::canonicalize(&candidate).unwrap_or_else(|_| candidate.clone());
+    if !seen.insert(canonical) {
+      continue;
+    }
+    if let Some(runtime) = JavaRuntime::probe(&candidate).await {
+      runtimes.push(runtime);
+    }
+  }
+  runtimes
+}
+
+/// Picks the Java runtime to run a server that needs Java `required`.
+///
+/// An explicitly `configured` executable is used as long as it is new enough.
+/// Otherwise the installed runtime closest to the required version is picked,
+/// since old servers can break on much newer Java releases.
+pub async fn select_runtime(
+  configured: Option<&Path>,
+  required: Option<u32>,
+) -> Result<JavaRuntime, JavaError> {
+  if let Some(executable) = configured {
+    let runtime = JavaRuntime::probe(executable)
+      .await
+      .ok_or_else(|| JavaError::UnknownVersion(executable.to_path_buf()))?;
+    return match required {
+      Some(required) if runtime.major_version < required => {
+        Err(JavaError::Unsuitable { runtime, required })
+      }
+      _ => Ok(runtime),
+    };
+  }
+
+  pick_runtime(find_runtimes().await, required)
+}
+
+/// Picks the runtime out of `runtimes` to run a server that needs Java
+/// `required`, as for [`select_runtime`].
+fn pick_runtime(
+  runtimes: Vec<JavaRuntime>,
+  required: Option<u32>,
+) -> Result<JavaRuntime, JavaError> {
+  let Some(required) = required else {
+    return runtimes.into_iter().next().ok_or(JavaError::NotFound);
+  };
+  if runtimes.is_empty() {
+    return Err(JavaError::NotFound);
+  }
+  let mut found: Vec<u32> = runtimes
+    .iter()
+    .map(|runtime| runtime.major_version)
+    .collect();
+  found.sort_unstable();
+  found.dedup();
+  runtimes
+    .into_iter()
+    .filter(|runtime| runtime.major_version >= required)
+    .min_by_key(|runtime| runtime.major_version)
+    .ok_or(JavaError::NoSuitableRuntime { required, found })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn runtime(version: &str) -> JavaRuntime {
+    JavaRuntime {
+      executable: PathBuf::from(format!("/opt/java/{version}/bin/java")),
+      version: version.to_string(),
+      major_version: major_version(version).unwrap(),
+    }
+  }
+
+  fn version_of(runtime: Result<JavaRuntime, JavaError>) -> String {
+    runtime.unwrap().version
+  }
+
+  #[test]
+  fn parses_version_output() {
+    let cases = [
+      (
+        "openjdk version \"17.0.8\" 2023-07-18\n\
+         OpenJDK Runtime Environment Temurin-17.0.8+7 (build 17.0.8+7)\n",
+        "17.0.8",
+        17,
+      ),
+      (
+        "java version \"1.8.0_382\"\n\
+         Java(TM) SE Runtime Environment (build 1.8.0_382-b12)\n",
+        "1.8.0_382",
+        8,
+      ),
+      ("openjdk version \"21\" 2023-09-19\n", "21", 21),
+      ("openjdk version \"22-ea\" 2024-03-19\n", "22-ea", 22),
+      ("openjdk version \"1.8.0_402-ea\"\n", "1.8.0_402-ea", 8),
+    ];
+    for (output, version, major) in cases {
+      assert_eq!(parse_version_output(output).as_deref(), Some(version));
+      assert_eq!(major_version(version), Some(major), "{version}");
+    }
+  }
+
+  #[test]
+  fn rejects_unknown_versions() {
+    assert_eq!(
+      parse_version_output("Error: could not find libjava.so\n"),
+      None
+    );
+    assert_eq!(parse_version_output("openjdk version \"17.0.8\n"), None);
+    assert_eq!(major_version(""), None);
+    assert_eq!(major_version("1"), None);
+    assert_eq!(major_version("ea"), None);
+  }
+
+  #[test]
+  fn picks_closest_runtime_at_or_above_required() {
+    let installed = || vec![runtime("21.0.1"), runtime("1.8.0_382"), runtime("17.0.8")];
+    assert_eq!(version_of(pick_runtime(installed(), Some(17))), "17.0.8");
+    assert_eq!(version_of(pick_runtime(installed(), Some(16))), "17.0.8");
+    assert_eq!(version_of(pick_runtime(installed(), Some(8))), "1.8.0_382");
+    assert_eq!(version_of(pick_runtime(installed(), Some(18))), "21.0.1");
+    // Without a requirement the most preferred runtime is used.
+    assert_eq!(version_of(pick_runtime(installed(), None)), "21.0.1");
+  }
+
+  #[test]
+  fn reports_runtimes_too_old() {
+    let installed = vec![runtime("17.0.8"), runtime("1.8.0_382"), runtime("17.0.2")];
+    assert!(matches!(
+      pick_runtime(installed, Some(21)),
+      Err(JavaError::NoSuitableRuntime { required: 21, found }) if found == [8, 17]
+    ));
+    assert!(matches!(
+      pick_runtime(Vec::new(), Some(17)),
+      Err(JavaError::NotFound)
+    ));
+  }
+}
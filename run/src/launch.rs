@@ -0,0 +1,272 @@
+use crate::flavor::Launch;
+use crate::memory;
+use clap::ValueEnum;
+use serde::Deserialize;
+use std::fmt::{self, Display, Formatter};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use thiserror::Error;
+
+pub const CONFIG_FILENAME: &str = "minecraft_tools.toml";
+
+pub(crate) const KIBIBYTE: u64 = 1024;
+pub(crate) const MEBIBYTE: u64 = 1024 * KIBIBYTE;
+pub(crate) const GIBIBYTE: u64 = 1024 * MEBIBYTE;
+
+const DEFAULT_HEAP_SIZE: HeapSize = HeapSize(1024 * MEBIBYTE);
+
+/// Heap size above which Aikar recommends a larger young generation.
+const AIKAR_LARGE_HEAP: u64 = 12 * GIBIBYTE;
+
+#[derive(Debug, Error)]
+pub enum ConfigError {
+  #[error("error in reading {CONFIG_FILENAME}")]
+  IoError(#[from] std::io::Error),
+  #[error("{CONFIG_FILENAME} is malformed: {0}")]
+  ParseError(#[from] toml::de::Error),
+}
+
+#[derive(Debug, Error)]
+#[error("invalid heap size \"{0}\", expected a number with an optional K, M or G suffix")]
+pub struct ParseHeapSizeError(String);
+
+/// A JVM heap size in bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(try_from = "String")]
+pub struct HeapSize(pub u64);
+
+impl FromStr for HeapSize {
+  type Err = ParseHeapSizeError;
+
+  fn from_str(size: &str) -> Result<Self, Self::Err> {
+    let (digits, unit) = match size.char_indices().last() {
+      Some((index, suffix)) if suffix.is_ascii_alphabetic() => {
+        (&size[..index], suffix.to_ascii_uppercase())
+      }
+      _ => (size, 'B'),
+    };
+    let multiplier = match unit {
+      'B' => 1,
+      'K' => KIBIBYTE,
+      'M' => MEBIBYTE,
+      'G' => GIBIBYTE,
+      _ => return Err(ParseHeapSizeError(size.to_string())),
+    };
+    digits
+      .parse::<u64>()
+      .ok()
+      .and_then(|value| value.checked_mul(multiplier))
+      .filter(|bytes| *bytes > 0)
+      .map(HeapSize)
+      .ok_or_else(|| ParseHeapSizeError(size.to_string()))
+  }
+}
+
+impl TryFrom<String> for HeapSize {
+  type Error = ParseHeapSizeError;
+
+  fn try_from(size: String) -> Result<Self, Self::Error> {
+    size.parse()
+  }
+}
+
+impl Display for HeapSize {
+  /// Formats the size the way the JVM's `-Xms` and `-Xmx` options take it.
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    match self.0 {
+      bytes if bytes % GIBIBYTE == 0 => write!(f, "{}G", bytes / GIBIBYTE),
+      bytes if bytes % MEBIBYTE == 0 => write!(f, "{}M", bytes / MEBIBYTE),
+      bytes if bytes % KIBIBYTE == 0 => write!(f, "{}K", bytes / KIBIBYTE),
+      bytes => write!(f, "{bytes}"),
+    }
+  }
+}
+
+/// How to choose the maximum heap size.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub enum HeapSetting {
+  /// Size the heap from the memory available to the server
+  Auto,
+  Fixed(HeapSize),
+}
+
+impl HeapSetting {
+  fn heap_size(self) -> HeapSize {
+    match self {
+      Self::Auto => memory::available_memory()
+        .map(memory::heap_size_for)
+        .unwrap_or(DEFAULT_HEAP_SIZE),
+      Self::Fixed(size) => size,
+    }
+  }
+}
+
+impl FromStr for HeapSetting {
+  type Err = ParseHeapSizeError;
+
+  fn from_str(setting: &str) -> Result<Self, Self::Err> {
+    if setting.eq_ignore_ascii_case("auto") {
+      Ok(Self::Auto)
+    } else {
+      setting.parse().map(Self::Fixed)
+    }
+  }
+}
+
+impl TryFrom<String> for HeapSetting {
+  type Error = ParseHeapSizeError;
+
+  fn try_from(setting: String) -> Result<Self, Self::Error> {
+    setting.parse()
+  }
+}
+
+/// Well-known sets of JVM flags for running Minecraft servers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum JvmPreset {
+  /// Aikar's G1GC tuning, see <https://docs.papermc.io/paper/aikars-flags>
+  Aikar,
+}
+
+impl JvmPreset {
+  fn args(self, max_memory: HeapSize) -> Vec<String> {
+    match self {
+      Self::Aikar => {
+        let large = max_memory.0 > AIKAR_LARGE_HEAP;
+        let (new_size, max_new_size, region_size, reserve, occupancy) = if large {
+          (40, 50, "16M", 15, 20)
+        } else {
+          (30, 40, "8M", 20, 15)
+        };
+        [
+          "-XX:+UseG1GC".to_string(),
+          "-XX:+ParallelRefProcEnabled".to_string(),
+          "-XX:MaxGCPauseMillis=200".to_string(),
+          "-XX:+UnlockExperimentalVMOptions".to_string(),
+          "-XX:+DisableExplicitGC".to_string(),
+          "-XX:+AlwaysPreTouch".to_string(),
+          format!("-XX:G1NewSizePercent={new_size}"),
+          format!("-XX:G1MaxNewSizePercent={max_new_size}"),
+          format!("-XX:G1HeapRegionSize={region_size}"),
+          format!("-XX:G1ReservePercent={reserve}"),
+          "-XX:G1HeapWastePercent=5".to_string(),
+          "-XX:G1MixedGCCountTarget=4".to_string(),
+          format!("-XX:InitiatingHeapOccupancyPercent={occupancy}"),
+          "-XX:G1MixedGCLiveThresholdPercent=90".to_string(),
+          "-XX:G1RSetUpdatingPauseTimePercent=5".to_string(),
+          "-XX:SurvivorRatio=32".to_string(),
+          "-XX:+PerfDisableSharedMem".to_string(),
+          "-XX:MaxTenuringThreshold=1".to_string(),
+          "-Dusing.aikars.flags=https://mcflags.emc.gs".to_string(),
+          "-Daikars.new.flags=true".to_string(),
+        ]
+        .into()
+      }
+    }
+  }
+}
+
+/// How to start the JVM, read from [`CONFIG_FILENAME`] in the server directory
+/// and overridable from the command line.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct LaunchConfig {
+  /// Java executable, detected from the installed runtimes if unset
+  pub java: Option<PathBuf>,
+  /// Initial heap size, defaulting to the maximum heap size
+  pub min_memory: Option<HeapSize>,
+  /// Maximum heap size, or `auto` to size it from the memory available
+  pub max_memory: Option<HeapSetting>,
+  pub preset: Option<JvmPreset>,
+  /// Extra arguments for the JVM, after those from the preset
+  pub jvm_args: Vec<String>,
+  /// Extra arguments for the server, after `nogui`
+  pub server_args: Vec<String>,
+}
+
+impl LaunchConfig {
+  pub fn load(directory: &Path) -> Result<Self, ConfigError> {
+    match fs::read_to_string(directory.join(CONFIG_FILENAME)) {
+      Ok(contents) => Ok(toml::from_str(&contents)?),
+      Err(error) if error.kind() == ErrorKind::NotFound => Ok(Self::default()),
+      Err(error) => Err(error.into()),
+    }
+  }
+
+  /// Fills in settings missing from `self` with those from `fallback`.
+  /// Argument lists are combined, with those from `fallback` first.
+  pub fn or(self, fallback: LaunchConfig) -> Self {
+    Self {
+      java: self.java.or(fallback.java),
+      min_memory: self.min_memory.or(fallback.min_memory),
+      max_memory: self.max_memory.or(fallback.max_memory),
+      preset: self.preset.or(fallback.preset),
+      jvm_args: [fallback.jvm_args, self.jvm_args].concat(),
+      server_args: [fallback.server_args, self.server_args].concat(),
+    }
+  }
+
+  pub fn command(&self, java: PathBuf, launch: Launch) -> ServerCommand {
+    let max_memory = self
+      .max_memory
+      .map_or(DEFAULT_HEAP_SIZE, HeapSetting::heap_size);
+    let min_memory = self.min_memory.unwrap_or(max_memory);
+    let mut jvm_args = vec![format!("-Xms{min_memory}"), format!("-Xmx{max_memory}")];
+    if let Some(preset) = self.preset {
+      jvm_args.extend(preset.args(max_memory));
+    }
+    jvm_args.extend(self.jvm_args.iter().cloned());
+
+    ServerCommand {
+      java,
+      jvm_args,
+      launch,
+      server_args: self.server_args.clone(),
+    }
+  }
+}
+
+/// The complete command line used to start the server.
+#[derive(Debug, Clone)]
+pub struct ServerCommand {
+  pub java: PathBuf,
+  pub jvm_args: Vec<String>,
+  pub launch: Launch,
+  pub server_args: Vec<String>,
+}
+
+impl ServerCommand {
+  /// Arguments to pass to [`Self::java`].
+  pub fn args(&self) -> Vec<String> {
+    let mut args = self.jvm_args.clone();
+    args.extend(self.launch.java_args());
+    args.push("nogui".to_string());
+    args.extend(self.server_args.iter().cloned());
+    args
+  }
+}
+
+/// Quotes `word` for a POSIX shell if it contains anything special.
+fn shell_quote(word: &str) -> String {
+  let is_plain = |c: char| c.is_ascii_alphanumeric() || "-_./=:@%+,".contains(c);
+  if !word.is_empty() && word.chars().all(is_plain) {
+    word.to_string()
+  } else {
+    format!("'{}'", word.replace('\'', "'\\''"))
+  }
+}
+
+impl Display for ServerCommand {
+  /// Formats the command line so that it can be pasted into a shell.
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    write!(f, "{}", shell_quote(&self.java.to_string_lossy()))?;
+    for arg in self.args() {
+      write!(f, " {}", shell_quote(&arg))?;
+    }
+    Ok(())
+  }
+}
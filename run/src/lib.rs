@@ -1,33 +1,84 @@
+pub mod blocking;
+pub mod download;
+pub mod eula;
+pub mod event;
+pub mod flavor;
+pub mod handle;
+pub mod integrity;
+pub mod java;
+pub mod launch;
+pub mod log_parser;
+pub mod memory;
+pub mod outcome;
+pub mod ping;
+pub mod properties;
+pub mod query;
+pub mod rcon;
+pub mod shutdown;
+pub mod state;
+pub mod supervisor;
+pub mod version;
+
+use download::DownloadError;
+use event::{EventHandler, EventSink, ServerEvent};
+use flavor::{Flavor, ServerBuild};
+use handle::ServerHandle;
+use java::{JavaError, JavaRuntime};
+use launch::{ConfigError, LaunchConfig, ServerCommand};
+use log_parser::{GameEvent, LogParser};
+use outcome::ServerOutcome;
+use properties::{ServerProperties, ServerSettings};
 use reqwest::header::{HeaderMap, HeaderValue};
 use reqwest::Client;
-use scraper::error::SelectorErrorKind;
-use scraper::{Html, Selector};
-use std::fs::{self, File};
-use std::io::Write;
-use std::path::PathBuf;
-use std::process::Stdio;
+use shutdown::{ShutdownMethod, ShutdownRequest};
+use state::{ServerState, StateError};
+use std::path::{Path, PathBuf};
+use std::process::{ExitStatus, Stdio};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+use std::{fs, future};
+use supervisor::{RestartPolicy, Supervisor};
 use thiserror::Error;
 use tokio::io::{
   AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader, BufWriter, Lines,
 };
-use tokio::process::Command;
-use tokio::sync::mpsc::{self, Sender};
+use tokio::process::{ChildStdin, Command};
+use tokio::sync::mpsc::{self, Receiver, Sender};
+use tokio::sync::{watch, Notify};
 use tokio::task::{JoinError, JoinHandle};
+use tokio::time;
+use version::{VersionRequest, DEFAULT_MANIFEST_URL};
 
 #[derive(Debug, Error)]
 pub enum RunMinecraftError {
   #[error("error in reading Minecraft world directory")]
   IoError(#[from] std::io::Error),
   #[error("generic error in processing Minecraft world")]
-  GenericError(#[from] Box<dyn std::error::Error>),
+  GenericError(#[from] Box<dyn std::error::Error + Send + Sync>),
   #[error("target directory does not contain a Minecraft world")]
   NoWorld,
-  #[error("error in fetching Minecraft webpage")]
+  #[error("target directory already contains a Minecraft server")]
+  AlreadyInitialized,
+  #[error("the Minecraft EULA ({}) has not been accepted", eula::EULA_URL)]
+  EulaNotAccepted,
+  #[error("error in fetching Minecraft version data")]
   CouldNotFetch(#[from] reqwest::Error),
-  #[error("error in decoding Minecraft webpage")]
-  BadSelector(#[from] SelectorErrorKind<'static>),
   #[error("error finding latest Minecraft server")]
   CouldNotFindServer,
+  #[error("Minecraft version \"{0}\" does not exist")]
+  UnknownVersion(String),
+  #[error("error in downloading Minecraft server: {0}")]
+  DownloadError(#[from] DownloadError),
+  #[error("server did not finish starting within {}s", .0.as_secs())]
+  StartupTimeout(Duration),
+  #[error("server installer failed with {0}")]
+  InstallFailed(ExitStatus),
+  #[error("{0}")]
+  ConfigError(#[from] ConfigError),
+  #[error("{0}")]
+  JavaError(#[from] JavaError),
+  #[error("error in persisting server state")]
+  StateError(#[from] StateError),
   #[error("threading error")]
   JoinError(#[from] JoinError),
   #[error("threading error")]
@@ -42,94 +93,191 @@ pub enum ThreadError {
   SendError(#[from] tokio::sync::mpsc::error::SendError<OutputMessage>),
 }
 
-fn path_exists(path: &PathBuf) -> bool {
+fn path_exists(path: &Path) -> bool {
   path.is_dir() || path.is_file()
 }
 
-fn is_likely_minecraft_directory(path: &PathBuf) -> bool {
-  let world_dir = path.join("world");
-  path_exists(&world_dir) || path_exists(&world_dir.join("level.dat"))
+/// Whether the server in `path` has generated the world named in its
+/// `server.properties`.
+fn has_world(path: &Path) -> std::io::Result<bool> {
+  let properties = ServerProperties::load(path)?;
+  Ok(path_exists(
+    &path.join(properties.level_name()).join("level.dat"),
+  ))
+}
+
+/// Whether `path` holds a server, either with a generated world or set up by
+/// `init` so that the server generates one on first start.
+fn is_likely_minecraft_directory(path: &Path) -> Result<bool, RunMinecraftError> {
+  Ok(has_world(path)? || ServerState::load(path)?.world_pending)
+}
+
+/// Whether the server in `path` has generated its world or finished being
+/// prepared, as opposed to being left over from an `init` that failed before
+/// the server was installed.
+fn has_been_set_up(path: &Path) -> Result<bool, RunMinecraftError> {
+  Ok(has_world(path)? || ServerState::load(path)?.version.is_some())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputSource {
+  Stdout,
+  Stderr,
 }
 
-#[derive(Debug)]
-pub struct OutputMessage(String);
+/// A line written by the server process.
+#[derive(Debug, Clone)]
+pub struct OutputMessage {
+  pub source: OutputSource,
+  /// When the runner read the line
+  pub received: SystemTime,
+  pub line: String,
+}
+
+#[derive(Clone)]
+pub struct RunOptions {
+  /// Server distribution to run, or [`None`] to reuse the one recorded in the
+  /// server directory (falling back to vanilla)
+  pub flavor: Option<Flavor>,
+  /// URL of the launcher version manifest used to resolve server versions
+  pub manifest_url: String,
+  /// Version to run, or [`None`] to reuse the version recorded in the server
+  /// directory (falling back to the latest release)
+  pub version: Option<VersionRequest>,
+  /// Whether the user agrees to the Minecraft EULA, which is recorded in the
+  /// server directory and must be accepted before the server can run
+  pub accept_eula: bool,
+  /// Receives notifications about the server as it is prepared and run
+  pub on_event: Option<EventHandler>,
+  /// Whether to stop the server gracefully when the process receives SIGINT
+  /// or SIGTERM
+  pub handle_signals: bool,
+  /// How long to wait for the server to exit after sending `stop` before
+  /// forcibly terminating it
+  pub shutdown_timeout: Duration,
+  /// How long the server may take to start before it is stopped and the run
+  /// fails, or [`None`] to wait indefinitely
+  pub ready_timeout: Option<Duration>,
+  /// When to restart the server after it exits on its own
+  pub restart_policy: RestartPolicy,
+  /// JVM settings, taking precedence over those configured in the server
+  /// directory
+  pub launch: LaunchConfig,
+}
+
+impl Default for RunOptions {
+  fn default() -> Self {
+    Self {
+      flavor: None,
+      manifest_url: DEFAULT_MANIFEST_URL.to_string(),
+      version: None,
+      accept_eula: false,
+      on_event: None,
+      handle_signals: false,
+      shutdown_timeout: Duration::from_secs(60),
+      ready_timeout: None,
+      restart_policy: RestartPolicy::default(),
+      launch: LaunchConfig::default(),
+    }
+  }
+}
+
+fn http_client() -> Result<Client, reqwest::Error> {
+  let mut headers = HeaderMap::new();
+  headers.insert("Accept-Encoding", HeaderValue::from_static("gzip"));
+  headers.insert(
+    "User-Agent",
+    HeaderValue::from_str(format!("Minecraft Tools v{}", env!("CARGO_PKG_VERSION")).as_str())
+      .expect("Could not create user-agent header"),
+  );
+  Client::builder().default_headers(headers).build()
+}
 
 fn run_grab_output_thread(
   mut reader: Lines<BufReader<impl 'static + AsyncRead + Send + Unpin>>,
+  source: OutputSource,
   sender: Sender<OutputMessage>,
 ) -> JoinHandle<Result<(), ThreadError>> {
   tokio::spawn(async move {
     while let Some(line) = reader.next_line().await? {
-      sender.send(OutputMessage(line)).await?;
+      sender
+        .send(OutputMessage {
+          source,
+          received: SystemTime::now(),
+          line,
+        })
+        .await?;
     }
 
     Ok(())
   })
 }
 
-#[tokio::main]
-pub async fn run_minecraft_server(
-  path: &PathBuf,
-  output_sink: impl AsyncWrite + Unpin,
-) -> Result<(), RunMinecraftError> {
-  if !is_likely_minecraft_directory(path) {
-    return Err(RunMinecraftError::NoWorld);
-  }
+/// Lists the versions offered by the server distribution selected in
+/// `options`, or recorded in the server directory at `path`.
+pub async fn list_server_versions(
+  path: &Path,
+  options: &RunOptions,
+) -> Result<Vec<String>, RunMinecraftError> {
+  let flavor = match options.flavor {
+    Some(flavor) => flavor,
+    None => ServerState::load(path)?.flavor.unwrap_or_default(),
+  };
+  flavor
+    .versions(&http_client()?, &options.manifest_url)
+    .await
+}
 
-  let mut headers = HeaderMap::new();
-  headers.insert("Accept-Encoding", HeaderValue::from_static("gzip"));
-  headers.insert(
-    "User-Agent",
-    HeaderValue::from_str(
-      format!("Minecraft Tools v{}", env!("CARGO_PKG_VERSION"))
-        .to_string()
-        .as_str(),
-    )
-    .expect("Could not create user-agent header"),
-  );
-  let client = Client::builder().default_headers(headers).build()?;
-  let webpage_html = client
-    .get("https://www.minecraft.net/en-us/download/server")
-    .send()
-    .await?
-    .text()
-    .await?;
-  let document = Html::parse_document(&webpage_html);
-  let selector = Selector::parse("a[aria-label='mincraft version']")?;
-  let link = document
-    .select(&selector)
-    .next()
-    .ok_or(RunMinecraftError::CouldNotFindServer)?;
-  let server_filename = link.inner_html();
-  let server_path = path.join(&server_filename);
-  let server_download_url = link
-    .value()
-    .attr("href")
-    .ok_or(RunMinecraftError::CouldNotFindServer)?;
-
-  if !path_exists(&server_path) {
-    let mut file = File::create(&server_path)?;
-    file.write(
-      &client
-        .get(server_download_url)
-        .send()
-        .await?
-        .bytes()
-        .await?,
-    )?;
-  }
+async fn write_command(writer: &mut ChildStdin, command: &str) -> std::io::Result<()> {
+  writer.write_all(command.as_bytes()).await?;
+  writer.write_u8(b'\n').await?;
+  writer.flush().await
+}
 
-  let eula_file = path.join("eula.txt");
-  if !path_exists(&eula_file) {
-    fs::write(eula_file, "eula=true")?;
-  }
+/// How often to check whether a starting server answers status pings.
+const READY_POLL_INTERVAL: Duration = Duration::from_secs(2);
 
-  let mut minecraft_server = Command::new("java")
+struct ProcessExit {
+  status: ExitStatus,
+  duration: Duration,
+  shutdown: Option<ShutdownMethod>,
+  /// How long the server was given to start, if it was stopped for taking
+  /// longer
+  startup_timeout: Option<Duration>,
+}
+
+/// Runs a single instance of the server until it exits, stopping it if a
+/// shutdown is requested in the meantime.
+async fn run_server_process(
+  path: &Path,
+  server_command: &ServerCommand,
+  options: &RunOptions,
+  events: &EventSink,
+  log_parser: &mut LogParser,
+  commands: &mut Receiver<String>,
+  shutdown_requested: &mut watch::Receiver<ShutdownRequest>,
+) -> Result<ProcessExit, RunMinecraftError> {
+  let mut command = Command::new(&server_command.java);
+  // Keep terminal signals away from the JVM so that shutdown goes through
+  // `stop`.
+  #[cfg(unix)]
+  command.process_group(0);
+  let mut minecraft_server = command
     .current_dir(path)
-    .args(["-Xmx1024M", "-Xms1024M", "-jar", &server_filename, "nogui"])
+    .args(server_command.args())
+    .stdin(Stdio::piped())
     .stdout(Stdio::piped())
     .stderr(Stdio::piped())
+    .kill_on_drop(true)
     .spawn()?;
+  let started = Instant::now();
+  events.emit(ServerEvent::Launched {
+    pid: minecraft_server.id(),
+  });
+  let mut stdin = minecraft_server
+    .stdin
+    .take()
+    .expect("Could not get Minecraft server stdin");
   let stdout = BufReader::new(
     minecraft_server
       .stdout
@@ -145,20 +293,413 @@ pub async fn run_minecraft_server(
   )
   .lines();
 
-  let mut output_sink = BufWriter::new(output_sink);
   let (sender, mut receiver) = mpsc::channel(1);
   let threads = [
-    run_grab_output_thread(stdout, sender.clone()),
-    run_grab_output_thread(stderr, sender),
+    run_grab_output_thread(stdout, OutputSource::Stdout, sender.clone()),
+    run_grab_output_thread(stderr, OutputSource::Stderr, sender),
   ];
-  while let Some(OutputMessage(message)) = receiver.recv().await {
-    output_sink.write_all(message.as_bytes()).await?;
-    output_sink.write_u8(b'\n').await?;
-    output_sink.flush().await?;
-  }
+  // Servers announce they are ready in the log, but not every flavor logs it
+  // the same way, so a status ping catches whatever the parser misses. The
+  // ping only starts once this process has logged that it is opening its
+  // port, since until then anything answering is some other server.
+  let done_logged = Notify::new();
+  let listening_logged = Notify::new();
+  let properties = ServerProperties::load(path)?;
+  let host = properties.server_ip().unwrap_or("127.0.0.1").to_string();
+  let pingable = async {
+    listening_logged.notified().await;
+    ping::wait_until_up(&host, properties.server_port(), READY_POLL_INTERVAL).await
+  };
+  let startup_deadline = async {
+    match options.ready_timeout {
+      Some(timeout) => time::sleep(timeout).await,
+      None => future::pending().await,
+    }
+  };
+  let forward_output = async {
+    while let Some(message) = receiver.recv().await {
+      let event = log_parser.parse(&message.line).and_then(|line| line.event);
+      events.emit_output(message).await;
+      if let Some(event) = event {
+        match event {
+          GameEvent::ServerListening { .. } => listening_logged.notify_one(),
+          GameEvent::ServerStarted { .. } => done_logged.notify_one(),
+          _ => {}
+        }
+        events.emit(ServerEvent::Game(event));
+      }
+    }
+  };
+  let supervise = async {
+    tokio::pin!(pingable, startup_deadline);
+    let mut ready = false;
+    loop {
+      tokio::select! {
+        // Type out commands queued before a shutdown request ahead of `stop`.
+        biased;
+
+        status = minecraft_server.wait() => {
+          return Ok(ProcessExit {
+            status: status?,
+            duration: started.elapsed(),
+            shutdown: None,
+            startup_timeout: None,
+          });
+        }
+        Some(command) = commands.recv() => {
+          // The console closes while the server is shutting down, at which
+          // point there is nothing left to type into.
+          let _ = write_command(&mut stdin, &command).await;
+        }
+        () = shutdown::requested(shutdown_requested, ShutdownRequest::Graceful) => {
+          events.emit(ServerEvent::Stopping);
+          let method = shutdown::stop_server(
+            &mut minecraft_server,
+            &mut stdin,
+            options.shutdown_timeout,
+            shutdown_requested,
+          )
+          .await?;
+          return Ok::<_, std::io::Error>(ProcessExit {
+            status: minecraft_server.wait().await?,
+            duration: started.elapsed(),
+            shutdown: Some(method),
+            startup_timeout: None,
+          });
+        }
+        () = done_logged.notified(), if !ready => {
+          ready = true;
+          events.emit(ServerEvent::Ready { after: started.elapsed() });
+        }
+        _ = &mut pingable, if !ready => {
+          ready = true;
+          events.emit(ServerEvent::Ready { after: started.elapsed() });
+        }
+        () = &mut startup_deadline, if !ready => {
+          events.emit(ServerEvent::Stopping);
+          shutdown::stop_server(
+            &mut minecraft_server,
+            &mut stdin,
+            options.shutdown_timeout,
+            shutdown_requested,
+          )
+          .await?;
+          return Ok(ProcessExit {
+            status: minecraft_server.wait().await?,
+            duration: started.elapsed(),
+            shutdown: None,
+            startup_timeout: options.ready_timeout,
+          });
+        }
+      }
+    }
+  };
+  let ((), exit) = tokio::join!(forward_output, supervise);
+  let exit = exit?;
   for thread in threads {
     thread.await??;
   }
+  events.emit(ServerEvent::Exited {
+    status: exit.status,
+  });
+  if let Some(timeout) = exit.startup_timeout {
+    return Err(RunMinecraftError::StartupTimeout(timeout));
+  }
+
+  Ok(exit)
+}
+
+/// The server a directory would run, as worked out before anything is
+/// downloaded or installed.
+#[derive(Debug, Clone)]
+pub struct ServerPlan {
+  pub flavor: Flavor,
+  pub build: ServerBuild,
+  pub java: JavaRuntime,
+  /// Command line the server would be launched with, which for a server
+  /// that still has to be installed is where its installer is expected to
+  /// put it
+  pub command: ServerCommand,
+}
+
+/// Works out which build and Java runtime the server in the directory at
+/// `path` would run on, without changing anything in the directory.
+async fn resolve_server(
+  path: &Path,
+  options: &RunOptions,
+  events: &EventSink,
+) -> Result<ServerPlan, RunMinecraftError> {
+  let launch_config = options.launch.clone().or(LaunchConfig::load(path)?);
+  let state = ServerState::load(path)?;
+  let flavor = options.flavor.or(state.flavor).unwrap_or_default();
+  let request = match (&options.version, &state.version) {
+    (Some(request), _) => request.clone(),
+    (None, Some(version)) => VersionRequest::Pinned(version.clone()),
+    (None, None) => VersionRequest::LatestRelease,
+  };
+  let build = flavor
+    .resolve(&http_client()?, &options.manifest_url, &request)
+    .await?;
+  let java = java::select_runtime(launch_config.java.as_deref(), build.java_version).await?;
+  events.emit(ServerEvent::JavaSelected(java.clone()));
+  let command = launch_config.command(java.executable.clone(), flavor.planned_launch(path, &build));
+
+  Ok(ServerPlan {
+    flavor,
+    build,
+    java,
+    command,
+  })
+}
+
+/// Gets the server in the directory at `path` ready to launch, downloading
+/// and installing it if necessary.
+async fn prepare_server(
+  path: &Path,
+  options: &RunOptions,
+  events: &EventSink,
+) -> Result<ServerCommand, RunMinecraftError> {
+  if !is_likely_minecraft_directory(path)? {
+    return Err(RunMinecraftError::NoWorld);
+  }
+  if !eula::is_accepted(path)? {
+    if !options.accept_eula {
+      return Err(RunMinecraftError::EulaNotAccepted);
+    }
+    eula::accept(path)?;
+  }
+  let ServerPlan {
+    flavor,
+    build,
+    java,
+    mut command,
+  } = resolve_server(path, options, events).await?;
+
+  let server_path = path.join(&build.filename);
+  let server_is_valid =
+    path_exists(&server_path) && integrity::check_file(&server_path, &build.artifact)?.is_none();
+  if !server_is_valid {
+    download::download_file(&http_client()?, &build.artifact, &server_path, |progress| {
+      events.emit(ServerEvent::DownloadProgress(progress))
+    })
+    .await?;
+  }
+  command.launch = flavor.install(path, &build, &java.executable).await?;
+
+  let mut state = ServerState::load(path)?;
+  let world_pending = state.world_pending && !has_world(path)?;
+  if state.flavor != Some(flavor)
+    || state.version.as_ref() != Some(&build.version)
+    || state.world_pending != world_pending
+  {
+    state.flavor = Some(flavor);
+    state.version = Some(build.version.clone());
+    state.world_pending = world_pending;
+    state.save(path)?;
+  }
 
+  Ok(command)
+}
+
+/// Works out what the server in the directory at `path` would run, without
+/// downloading, installing or recording anything.
+pub async fn resolve_minecraft_server(
+  path: &Path,
+  options: &RunOptions,
+) -> Result<ServerPlan, RunMinecraftError> {
+  if !is_likely_minecraft_directory(path)? {
+    return Err(RunMinecraftError::NoWorld);
+  }
+  let (events, _) = EventSink::new(options.on_event.clone(), None);
+  resolve_server(path, options, &events).await
+}
+
+/// Downloads and installs the server in the directory at `path` without
+/// starting it, returning the command line it would be launched with.
+pub async fn prepare_minecraft_server(
+  path: &Path,
+  options: &RunOptions,
+) -> Result<ServerCommand, RunMinecraftError> {
+  let (events, _) = EventSink::new(options.on_event.clone(), None);
+  prepare_server(path, options, &events).await
+}
+
+/// Sets up a new server in the directory at `path`, creating it if needed,
+/// with a `server.properties` made from `settings`. The server is downloaded
+/// and installed so that it is ready to run, and generates its world on first
+/// start.
+pub async fn init_minecraft_server(
+  path: &Path,
+  options: &RunOptions,
+  settings: &ServerSettings,
+) -> Result<(), RunMinecraftError> {
+  if has_been_set_up(path)? {
+    return Err(RunMinecraftError::AlreadyInitialized);
+  }
+  if !options.accept_eula && !eula::is_accepted(path)? {
+    return Err(RunMinecraftError::EulaNotAccepted);
+  }
+
+  fs::create_dir_all(path)?;
+  // Keep what an earlier attempt that failed part way wrote.
+  let mut properties = ServerProperties::load(path)?;
+  settings.apply(&mut properties);
+  properties.save(path)?;
+  let mut state = ServerState::load(path)?;
+  state.world_pending = true;
+  state.save(path)?;
+  prepare_minecraft_server(path, options).await?;
   Ok(())
 }
+
+/// Runs the server until it exits for good, restarting it as allowed by the
+/// configured restart policy.
+async fn supervise_server(
+  path: &Path,
+  server_command: &ServerCommand,
+  options: &RunOptions,
+  events: &EventSink,
+  mut commands: Receiver<String>,
+  mut shutdown_requested: watch::Receiver<ShutdownRequest>,
+) -> Result<ServerOutcome, RunMinecraftError> {
+  let mut supervisor = Supervisor::new(options.restart_policy.clone());
+  let mut log_parser = LogParser::new();
+  let mut restarts = 0;
+  loop {
+    let previous_crash_reports = supervisor::crash_reports(path)?;
+    let exit = run_server_process(
+      path,
+      server_command,
+      options,
+      events,
+      &mut log_parser,
+      &mut commands,
+      &mut shutdown_requested,
+    )
+    .await?;
+    let crash_report = supervisor::new_crash_report(path, &previous_crash_reports)?;
+    let outcome = ServerOutcome::new(
+      exit.status,
+      exit.duration,
+      crash_report,
+      exit.shutdown,
+      restarts,
+    );
+    if outcome.shutdown.is_some() {
+      return Ok(outcome);
+    }
+
+    let Some(delay) = supervisor.next_restart(exit.status, outcome.crash_report.is_some()) else {
+      return Ok(outcome);
+    };
+    restarts += 1;
+    events.emit(ServerEvent::Restarting {
+      attempt: restarts,
+      status: exit.status,
+      crash_report: outcome.crash_report.clone(),
+      delay,
+    });
+    tokio::select! {
+      _ = time::sleep(delay) => {}
+      () = shutdown::requested(&mut shutdown_requested, ShutdownRequest::Graceful) => {
+        return Ok(outcome);
+      }
+    }
+  }
+}
+
+async fn start_server(
+  path: PathBuf,
+  options: RunOptions,
+  console: Option<Sender<OutputMessage>>,
+) -> Result<ServerHandle, RunMinecraftError> {
+  let (events, event_stream) = EventSink::new(options.on_event.clone(), console);
+  let server_command = prepare_server(&path, &options, &events).await?;
+
+  let (command_sender, commands) = mpsc::channel(16);
+  let (request_shutdown, shutdown_requested) = watch::channel(ShutdownRequest::None);
+  let request_shutdown = Arc::new(request_shutdown);
+  let signal_thread = options.handle_signals.then(|| {
+    let request_shutdown = request_shutdown.clone();
+    tokio::spawn(async move {
+      // The first signal stops the server gracefully, and a second one while
+      // it is stopping forces it down.
+      for request in [ShutdownRequest::Graceful, ShutdownRequest::Forced] {
+        if shutdown::shutdown_signal().await.is_err() {
+          break;
+        }
+        shutdown::request(&request_shutdown, request);
+      }
+    })
+  });
+  let task = tokio::spawn(async move {
+    let outcome = supervise_server(
+      &path,
+      &server_command,
+      &options,
+      &events,
+      commands,
+      shutdown_requested,
+    )
+    .await;
+    if let Some(signal_thread) = signal_thread {
+      signal_thread.abort();
+    }
+    outcome
+  });
+
+  Ok(ServerHandle::new(
+    command_sender,
+    event_stream,
+    request_shutdown,
+    task,
+  ))
+}
+
+/// Prepares and starts the Minecraft server in the directory at `path`,
+/// returning once it has been launched.
+///
+/// The server keeps running in the background, restarting as allowed by the
+/// configured restart policy, and is controlled through the returned handle.
+pub async fn start_minecraft_server(
+  path: PathBuf,
+  options: RunOptions,
+) -> Result<ServerHandle, RunMinecraftError> {
+  start_server(path, options, None).await
+}
+
+/// Runs the Minecraft server in the directory at `path` until it exits,
+/// restarting it as allowed by the configured restart policy.
+///
+/// Lines received on `commands` are typed into the server console and lines
+/// written by the server are copied to `output_sink`.
+pub async fn run_minecraft_server(
+  path: &Path,
+  options: &RunOptions,
+  mut commands: Receiver<String>,
+  output_sink: impl AsyncWrite + Unpin,
+) -> Result<ServerOutcome, RunMinecraftError> {
+  // Every line has to reach `output_sink`, so it gets its own console that
+  // holds up the server while the sink catches up.
+  let (console, mut output) = mpsc::channel(1);
+  let mut server = start_server(path.to_path_buf(), options.clone(), Some(console)).await?;
+  drop(server.take_events());
+  let console = server.commands();
+  let command_thread = tokio::spawn(async move {
+    while let Some(command) = commands.recv().await {
+      if console.send(command).await.is_err() {
+        break;
+      }
+    }
+  });
+
+  let mut output_sink = BufWriter::new(output_sink);
+  while let Some(message) = output.recv().await {
+    output_sink.write_all(message.line.as_bytes()).await?;
+    output_sink.write_u8(b'\n').await?;
+    output_sink.flush().await?;
+  }
+  command_thread.abort();
+
+  server.wait().await
+}
@@ -0,0 +1,564 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Death messages as they follow the player's name, from the vanilla
+/// language file.
+const DEATH_PHRASES: &[&str] = &[
+  "was shot by",
+  "was pummeled by",
+  "was pricked to death",
+  "walked into a cactus",
+  "walked into the danger zone",
+  "drowned",
+  "experienced kinetic energy",
+  "blew up",
+  "was blown up by",
+  "was killed by",
+  "hit the ground too hard",
+  "fell from a high place",
+  "fell off",
+  "fell while climbing",
+  "was doomed to fall",
+  "was impaled on a stalagmite",
+  "was skewered by a falling stalactite",
+  "was squashed by",
+  "was squished too much",
+  "went up in flames",
+  "walked into fire",
+  "burned to death",
+  "was burnt to a crisp",
+  "went off with a bang",
+  "tried to swim in lava",
+  "was struck by lightning",
+  "discovered the floor was lava",
+  "was slain by",
+  "was fireballed by",
+  "was stung to death",
+  "was obliterated by a sonically-charged shriek",
+  "starved to death",
+  "suffocated in a wall",
+  "was poked to death",
+  "was impaled by",
+  "fell out of the world",
+  "didn't want to live in the same world as",
+  "withered away",
+  "was roasted in dragon's breath",
+  "froze to death",
+  "was frozen to death by",
+  "was killed",
+  "died",
+];
+
+/// Names `/say` gives when it is not run by a player: the server console,
+/// RCON and command blocks.
+const CONSOLE_SOURCES: &[&str] = &["Server", "Rcon", "@"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogLevel {
+  Trace,
+  Debug,
+  Info,
+  Warn,
+  Error,
+  Fatal,
+}
+
+impl FromStr for LogLevel {
+  type Err = ();
+
+  fn from_str(level: &str) -> Result<Self, Self::Err> {
+    match level {
+      "TRACE" => Ok(Self::Trace),
+      "DEBUG" => Ok(Self::Debug),
+      "INFO" => Ok(Self::Info),
+      "WARN" => Ok(Self::Warn),
+      "ERROR" => Ok(Self::Error),
+      "FATAL" => Ok(Self::Fatal),
+      _ => Err(()),
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LogTime {
+  pub hour: u8,
+  pub minute: u8,
+  pub second: u8,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AdvancementKind {
+  Advancement,
+  Goal,
+  Challenge,
+}
+
+/// Something that happened on the server, recognised from its log.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GameEvent {
+  /// The server is about to open its port, at `address` such as `*:25565`
+  ServerListening {
+    address: String,
+  },
+  /// The server finished starting up and is accepting players
+  ServerStarted {
+    startup_time: Duration,
+  },
+  PlayerJoined {
+    name: String,
+    uuid: Option<String>,
+  },
+  PlayerLeft {
+    name: String,
+    uuid: Option<String>,
+  },
+  Chat {
+    sender: String,
+    message: String,
+  },
+  /// Feedback broadcast to operators when someone runs a command
+  CommandFeedback {
+    source: String,
+    message: String,
+  },
+  Death {
+    player: String,
+    message: String,
+  },
+  Advancement {
+    player: String,
+    kind: AdvancementKind,
+    title: String,
+  },
+  /// The server is falling behind on ticks
+  CantKeepUp {
+    behind: Duration,
+    ticks: u64,
+  },
+}
+
+/// A line of server output in the standard log format.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogLine {
+  pub time: LogTime,
+  /// Thread which logged the line, which Paper and Spigot omit
+  pub thread: Option<String>,
+  pub level: LogLevel,
+  pub message: String,
+  pub event: Option<GameEvent>,
+}
+
+fn parse_time(text: &str) -> Option<LogTime> {
+  let mut parts = text.split(':').map(|part| part.parse().ok());
+  let time = LogTime {
+    hour: parts.next()??,
+    minute: parts.next()??,
+    second: parts.next()??,
+  };
+  parts.next().is_none().then_some(time)
+}
+
+fn is_player_name(name: &str) -> bool {
+  (3..=16).contains(&name.len()) && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Splits `[Title]` off the end of an advancement message.
+fn bracketed(text: &str) -> Option<&str> {
+  text.strip_prefix('[')?.strip_suffix(']')
+}
+
+fn parse_started(message: &str) -> Option<GameEvent> {
+  let seconds = message
+    .strip_prefix("Done (")?
+    .split_once("s)!")?
+    .0
+    .parse()
+    .ok()?;
+  Some(GameEvent::ServerStarted {
+    startup_time: Duration::from_secs_f64(seconds),
+  })
+}
+
+fn parse_listening(message: &str) -> Option<GameEvent> {
+  let address = message.strip_prefix("Starting Minecraft server on ")?;
+  Some(GameEvent::ServerListening {
+    address: address.to_string(),
+  })
+}
+
+fn parse_cant_keep_up(message: &str) -> Option<GameEvent> {
+  let (milliseconds, ticks) = message
+    .strip_prefix("Can't keep up! Is the server overloaded? Running ")?
+    .strip_suffix(" ticks behind")?
+    .split_once("ms or ")?;
+  Some(GameEvent::CantKeepUp {
+    behind: Duration::from_millis(milliseconds.parse().ok()?),
+    ticks: ticks.parse().ok()?,
+  })
+}
+
+fn parse_chat(message: &str) -> Option<GameEvent> {
+  let message = message.strip_prefix("[Not Secure] ").unwrap_or(message);
+  let (sender, text) = message.strip_prefix('<')?.split_once("> ")?;
+  Some(GameEvent::Chat {
+    sender: sender.to_string(),
+    message: text.to_string(),
+  })
+}
+
+/// Parses a `/say` broadcast, such as `[Server] Restarting soon`, from the
+/// console or a player in `players`. Plugins log in the same shape, such as
+/// `[LuckPerms] Loading configuration...`, so other senders are not chat.
+fn parse_announcement(message: &str, players: &HashMap<String, String>) -> Option<GameEvent> {
+  let (sender, text) = message.strip_prefix('[')?.split_once("] ")?;
+  (CONSOLE_SOURCES.contains(&sender) || players.contains_key(sender)).then(|| GameEvent::Chat {
+    sender: sender.to_string(),
+    message: text.to_string(),
+  })
+}
+
+fn parse_command_feedback(message: &str) -> Option<GameEvent> {
+  let (source, text) = bracketed(message)?.split_once(": ")?;
+  Some(GameEvent::CommandFeedback {
+    source: source.to_string(),
+    message: text.to_string(),
+  })
+}
+
+fn parse_advancement(message: &str) -> Option<GameEvent> {
+  [
+    (" has made the advancement ", AdvancementKind::Advancement),
+    (" has reached the goal ", AdvancementKind::Goal),
+    (" has completed the challenge ", AdvancementKind::Challenge),
+  ]
+  .into_iter()
+  .find_map(|(separator, kind)| {
+    let (player, title) = message.split_once(separator)?;
+    Some(GameEvent::Advancement {
+      player: player.to_string(),
+      kind,
+      title: bracketed(title)?.to_string(),
+    })
+  })
+}
+
+fn parse_death(message: &str) -> Option<GameEvent> {
+  let (player, rest) = message.split_once(' ')?;
+  let is_death = DEATH_PHRASES.iter().any(|phrase| {
+    rest
+      .strip_prefix(phrase)
+      .is_some_and(|after| after.is_empty() || after.starts_with(' '))
+  });
+  (is_player_name(player) && is_death).then(|| GameEvent::Death {
+    player: player.to_string(),
+    message: message.to_string(),
+  })
+}
+
+/// Parses server output, remembering player UUIDs between lines so that they
+/// can be attached to join and leave events, and so that `/say` from players
+/// can be told apart from plugin messages.
+#[derive(Debug, Default)]
+pub struct LogParser {
+  uuids: HashMap<String, String>,
+}
+
+impl LogParser {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Parses a line of output, returning [`None`] if it is not in the
+  /// standard log format.
+  pub fn parse(&mut self, line: &str) -> Option<LogLine> {
+    let rest = line.strip_prefix('[')?;
+    let (header, rest) = rest.split_once(']')?;
+    let (time, thread, level, rest) = match header.split_once(' ') {
+      // `[HH:MM:SS LEVEL]: message`, as printed by Paper and Spigot
+      Some((time, level)) => (parse_time(time)?, None, level.parse().ok()?, rest),
+      // `[HH:MM:SS] [thread/LEVEL]: message`
+      None => {
+        let (source, rest) = rest.strip_prefix(" [")?.split_once(']')?;
+        let (thread, level) = source.rsplit_once('/')?;
+        (
+          parse_time(header)?,
+          Some(thread.to_string()),
+          level.parse().ok()?,
+          rest,
+        )
+      }
+    };
+    // Forge names the logger after the thread
+    let rest = match rest.strip_prefix(" [") {
+      Some(logger) => logger.split_once(']')?.1,
+      None => rest,
+    };
+    let message = rest.strip_prefix(':')?;
+    let message = message.strip_prefix(' ').unwrap_or(message);
+
+    Some(LogLine {
+      time,
+      thread,
+      level,
+      message: message.to_string(),
+      event: self.parse_event(message),
+    })
+  }
+
+  fn parse_event(&mut self, message: &str) -> Option<GameEvent> {
+    if let Some((name, uuid)) = message
+      .strip_prefix("UUID of player ")
+      .and_then(|rest| rest.split_once(" is "))
+    {
+      self.uuids.insert(name.to_string(), uuid.to_string());
+      return None;
+    }
+
+    // Anything a player can type ends up in chat or `/say` lines, so those
+    // are recognised first to keep them from passing as joins and leaves.
+    if let Some(event) = parse_chat(message)
+      .or_else(|| parse_command_feedback(message))
+      .or_else(|| parse_announcement(message, &self.uuids))
+    {
+      return Some(event);
+    }
+
+    if let Some(name) = message.strip_suffix(" joined the game") {
+      let name = name
+        .split_once(" (formerly known as ")
+        .map_or(name, |(name, _)| name);
+      return is_player_name(name).then(|| GameEvent::PlayerJoined {
+        name: name.to_string(),
+        uuid: self.uuids.get(name).cloned(),
+      });
+    }
+
+    if let Some(name) = message
+      .strip_suffix(" left the game")
+      .filter(|name| is_player_name(name))
+    {
+      return Some(GameEvent::PlayerLeft {
+        name: name.to_string(),
+        uuid: self.uuids.remove(name),
+      });
+    }
+
+    parse_started(message)
+      .or_else(|| parse_listening(message))
+      .or_else(|| parse_cant_keep_up(message))
+      .or_else(|| parse_advancement(message))
+      .or_else(|| parse_death(message))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const VANILLA: &str = include_str!("../tests/fixtures/logs/vanilla.log");
+  const PAPER: &str = include_str!("../tests/fixtures/logs/paper.log");
+  const FORGE: &str = include_str!("../tests/fixtures/logs/forge.log");
+  const NO_EVENTS: &str = include_str!("../tests/fixtures/logs/no_events.log");
+  const SPOOFED: &str = include_str!("../tests/fixtures/logs/spoofed.log");
+  const UNPARSED: &str = include_str!("../tests/fixtures/logs/unparsed.log");
+
+  const STEVE_UUID: &str = "8667ba71-b85a-4004-af54-457a9734eed7";
+  const ALEX_UUID: &str = "61699b2e-d327-4a01-9f1e-0ea8c3f06bc6";
+
+  fn parse_all(log: &str) -> Vec<Option<LogLine>> {
+    let mut parser = LogParser::new();
+    log.lines().map(|line| parser.parse(line)).collect()
+  }
+
+  fn events(log: &str) -> Vec<GameEvent> {
+    parse_all(log)
+      .into_iter()
+      .map(|line| line.expect("Fixture line should parse"))
+      .filter_map(|line| line.event)
+      .collect()
+  }
+
+  fn chat(sender: &str, message: &str) -> GameEvent {
+    GameEvent::Chat {
+      sender: sender.to_string(),
+      message: message.to_string(),
+    }
+  }
+
+  fn advancement(kind: AdvancementKind, title: &str) -> GameEvent {
+    GameEvent::Advancement {
+      player: "Steve".to_string(),
+      kind,
+      title: title.to_string(),
+    }
+  }
+
+  #[test]
+  fn recognises_vanilla_events() {
+    assert_eq!(
+      events(VANILLA),
+      [
+        GameEvent::ServerListening {
+          address: "*:25565".to_string(),
+        },
+        GameEvent::ServerStarted {
+          startup_time: Duration::from_millis(3215),
+        },
+        GameEvent::PlayerJoined {
+          name: "Steve".to_string(),
+          uuid: Some(STEVE_UUID.to_string()),
+        },
+        chat("Steve", "hello there"),
+        chat("Steve", "sent without a signature"),
+        GameEvent::CommandFeedback {
+          source: "Steve".to_string(),
+          message: "Set the time to 1000".to_string(),
+        },
+        chat("Server", "Restarting in 5 minutes"),
+        advancement(AdvancementKind::Advancement, "Stone Age"),
+        advancement(AdvancementKind::Goal, "Sky's the Limit"),
+        advancement(AdvancementKind::Challenge, "Arbalistic"),
+        GameEvent::Death {
+          player: "Steve".to_string(),
+          message: "Steve was slain by Zombie".to_string(),
+        },
+        GameEvent::Death {
+          player: "Steve".to_string(),
+          message: "Steve drowned".to_string(),
+        },
+        GameEvent::CantKeepUp {
+          behind: Duration::from_millis(2543),
+          ticks: 50,
+        },
+        GameEvent::PlayerLeft {
+          name: "Steve".to_string(),
+          uuid: Some(STEVE_UUID.to_string()),
+        },
+        GameEvent::PlayerJoined {
+          name: "Alex".to_string(),
+          uuid: None,
+        },
+      ]
+    );
+  }
+
+  #[test]
+  fn recognises_paper_events() {
+    assert_eq!(
+      events(PAPER),
+      [
+        GameEvent::ServerListening {
+          address: "0.0.0.0:25566".to_string(),
+        },
+        GameEvent::ServerStarted {
+          startup_time: Duration::from_millis(6012),
+        },
+        GameEvent::PlayerJoined {
+          name: "Alex".to_string(),
+          uuid: Some(ALEX_UUID.to_string()),
+        },
+        chat("Alex", "hi from paper"),
+        chat("Alex", "hello everyone"),
+        GameEvent::CantKeepUp {
+          behind: Duration::from_millis(5012),
+          ticks: 100,
+        },
+        GameEvent::PlayerLeft {
+          name: "Alex".to_string(),
+          uuid: Some(ALEX_UUID.to_string()),
+        },
+      ]
+    );
+  }
+
+  #[test]
+  fn recognises_forge_events() {
+    assert_eq!(
+      events(FORGE),
+      [
+        GameEvent::ServerStarted {
+          startup_time: Duration::from_millis(12345),
+        },
+        GameEvent::PlayerJoined {
+          name: "Steve".to_string(),
+          uuid: None,
+        },
+      ]
+    );
+  }
+
+  #[test]
+  fn parses_vanilla_header() {
+    let line = parse_all(VANILLA)[16].clone().unwrap();
+    assert_eq!(
+      line.time,
+      LogTime {
+        hour: 12,
+        minute: 1,
+        second: 45,
+      }
+    );
+    assert_eq!(line.thread.as_deref(), Some("Server thread"));
+    assert_eq!(line.level, LogLevel::Warn);
+    assert!(line.message.starts_with("Can't keep up!"));
+  }
+
+  #[test]
+  fn parses_paper_header() {
+    let line = parse_all(PAPER)[2].clone().unwrap();
+    assert_eq!(
+      line.time,
+      LogTime {
+        hour: 12,
+        minute: 0,
+        second: 6,
+      }
+    );
+    assert_eq!(line.thread, None);
+    assert_eq!(line.level, LogLevel::Info);
+    assert_eq!(line.message, "Done (6.012s)! For help, type \"help\"");
+  }
+
+  #[test]
+  fn skips_forge_logger() {
+    let lines = parse_all(FORGE);
+    let line = lines[0].clone().unwrap();
+    assert_eq!(line.thread.as_deref(), Some("main"));
+    assert_eq!(
+      line.message,
+      "ModLauncher running: args [--launchTarget, forgeserver]"
+    );
+    let line = lines[1].clone().unwrap();
+    assert_eq!(line.thread.as_deref(), Some("Server thread"));
+    assert_eq!(line.message, "Done (12.345s)! For help, type \"help\"");
+  }
+
+  #[test]
+  fn ignores_lines_without_events() {
+    for (line, parsed) in NO_EVENTS.lines().zip(parse_all(NO_EVENTS)) {
+      let parsed = parsed.unwrap_or_else(|| panic!("{line:?} should parse"));
+      assert_eq!(parsed.event, None, "{line:?} should have no event");
+    }
+  }
+
+  #[test]
+  fn keeps_typed_text_as_chat() {
+    assert_eq!(
+      events(SPOOFED),
+      [
+        chat("Mallory", "Steve joined the game"),
+        chat("Mallory", "Steve left the game"),
+        chat("Mallory", "Steve joined the game"),
+        chat("Mallory", "Steve was slain by Zombie"),
+      ]
+    );
+  }
+
+  #[test]
+  fn rejects_lines_in_other_formats() {
+    for (line, parsed) in UNPARSED.lines().zip(parse_all(UNPARSED)) {
+      assert_eq!(parsed, None, "{line:?} should not parse");
+    }
+  }
+}
@@ -1,19 +1,753 @@
-use clap::Parser;
-use std::path::PathBuf;
-use tokio::io::stdout;
+use clap::{ArgAction, Parser, Subcommand, ValueEnum};
+use run::download::DownloadProgress;
+use run::event::ServerEvent;
+use run::flavor::Flavor;
+use run::launch::{HeapSetting, HeapSize, JvmPreset, LaunchConfig};
+use run::outcome::ServerOutcome;
+use run::ping::ServerStatus;
+use run::properties::{
+  Difficulty, GameMode, ServerProperties, ServerSettings, DEFAULT_SERVER_PORT,
+};
+use run::query::QueryStats;
+use run::rcon::RconClient;
+use run::shutdown::ShutdownMethod;
+use run::supervisor::{RestartMode, RestartPolicy};
+use run::version::VersionRequest;
+use run::{OutputMessage, OutputSource, RunMinecraftError, RunOptions};
+use std::io::{stdin, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use tokio::io;
+use tokio::sync::mpsc::{self, Receiver};
+
+/// Where to print lines the server writes to stderr.
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum StderrMode {
+  /// Print alongside stdout
+  Merged,
+  /// Print to our own stderr
+  Separate,
+  /// Print alongside stdout in red
+  Colored,
+}
+
+// Which server distribution and version to use, shared between commands.
+#[derive(clap::Args, Debug)]
+struct ServerSelection {
+  /// Server distribution to run
+  #[arg(long, value_enum)]
+  flavor: Option<Flavor>,
+  /// URL of the Minecraft version manifest
+  #[arg(long, default_value = run::version::DEFAULT_MANIFEST_URL)]
+  manifest_url: String,
+  /// Run a specific Minecraft version
+  #[arg(long, conflicts_with_all = ["latest_release", "latest_snapshot"])]
+  version: Option<String>,
+  /// Upgrade to the latest Minecraft release
+  #[arg(long, conflicts_with = "latest_snapshot")]
+  latest_release: bool,
+  /// Upgrade to the latest Minecraft snapshot
+  #[arg(long)]
+  latest_snapshot: bool,
+}
+
+impl ServerSelection {
+  fn version_request(&self) -> Option<VersionRequest> {
+    if let Some(version) = &self.version {
+      Some(VersionRequest::Pinned(version.clone()))
+    } else if self.latest_release {
+      Some(VersionRequest::LatestRelease)
+    } else if self.latest_snapshot {
+      Some(VersionRequest::LatestSnapshot)
+    } else {
+      None
+    }
+  }
+}
+
+#[derive(clap::Args, Debug)]
+struct InitArgs {
+  /// Directory to set up the server in, created if it does not exist
+  directory: PathBuf,
+  /// Agree to the Minecraft EULA (https://aka.ms/MinecraftEULA)
+  #[arg(long)]
+  accept_eula: bool,
+  #[command(flatten)]
+  server: ServerSelection,
+  /// Java executable to run installers with
+  #[arg(long)]
+  java: Option<PathBuf>,
+  /// Seed for the generated world
+  #[arg(long)]
+  seed: Option<String>,
+  /// Game mode for new players
+  #[arg(long, value_enum)]
+  gamemode: Option<GameMode>,
+  #[arg(long, value_enum)]
+  difficulty: Option<Difficulty>,
+  /// Port to listen on
+  #[arg(long)]
+  port: Option<u16>,
+  /// Message shown in the server list
+  #[arg(long)]
+  motd: Option<String>,
+}
+
+#[derive(Subcommand, Debug)]
+enum ConfigAction {
+  /// Print the value of a setting
+  Get { key: String },
+  /// Change a setting, keeping the rest of the file as it is
+  Set { key: String, value: String },
+  /// Print all settings
+  List,
+}
+
+#[derive(clap::Args, Debug)]
+struct ConfigArgs {
+  /// Directory containing the minecraft server
+  directory: PathBuf,
+  #[command(subcommand)]
+  action: ConfigAction,
+}
+
+#[derive(clap::Args, Debug)]
+struct RconArgs {
+  /// Directory containing the minecraft server, whose server.properties
+  /// provides the connection settings
+  directory: PathBuf,
+  /// Host to connect to (defaults to server-ip, or localhost)
+  #[arg(long)]
+  host: Option<String>,
+  /// Port to connect to (defaults to rcon.port)
+  #[arg(long)]
+  port: Option<u16>,
+  /// RCON password (defaults to rcon.password)
+  #[arg(long)]
+  password: Option<String>,
+  /// Command to run, or none to read commands from stdin
+  #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+  command: Vec<String>,
+}
+
+/// Which server to ask about itself over the network.
+#[derive(clap::Args, Debug)]
+struct ServerAddressArgs {
+  /// Directory containing the minecraft server, or the host[:port] of a
+  /// server to ask
+  target: String,
+  /// Seconds to wait for the server to answer
+  #[arg(long, default_value_t = 5)]
+  timeout: u64,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+  /// Set up a new server, ready to run
+  Init(InitArgs),
+  /// Read or change settings in server.properties
+  Config(ConfigArgs),
+  /// Run commands on a running server over RCON
+  Rcon(RconArgs),
+  /// Show what a server reports in the multiplayer server list
+  Status(ServerAddressArgs),
+  /// Show the full player list and plugins of a server with enable-query set
+  Query(ServerAddressArgs),
+}
 
 #[derive(Parser, Debug)]
-#[command(author, version, about, long_about = None)]
+#[command(
+  author,
+  version,
+  about,
+  long_about = None,
+  disable_version_flag = true,
+  args_conflicts_with_subcommands = true,
+  subcommand_negates_reqs = true
+)]
 struct Args {
+  #[command(subcommand)]
+  command: Option<Command>,
   /// Directory containing the minecraft server
-  directory: PathBuf,
+  #[arg(required = true)]
+  directory: Option<PathBuf>,
+  /// Agree to the Minecraft EULA (https://aka.ms/MinecraftEULA)
+  #[arg(long)]
+  accept_eula: bool,
+  #[command(flatten)]
+  server: ServerSelection,
+  /// List the versions offered by the server distribution and exit
+  #[arg(long)]
+  list_versions: bool,
+  /// Seconds to wait for the server to stop on SIGINT/SIGTERM before
+  /// terminating it
+  #[arg(long, default_value_t = 60)]
+  shutdown_timeout: u64,
+  /// Seconds to give the server to finish starting before stopping it and
+  /// failing
+  #[arg(long)]
+  ready_timeout: Option<u64>,
+  /// When to restart the server after it exits on its own
+  #[arg(long, value_enum, default_value_t = RestartMode::Never)]
+  restart: RestartMode,
+  /// Most restarts allowed within the restart window before giving up
+  #[arg(long, default_value_t = 5)]
+  max_restarts: u32,
+  /// Length of the restart window in seconds
+  #[arg(long, default_value_t = 600)]
+  restart_window: u64,
+  /// Seconds to wait before restarting, doubled for each recent restart
+  #[arg(long, default_value_t = 5)]
+  restart_backoff: u64,
+  /// Java executable to run the server with
+  #[arg(long)]
+  java: Option<PathBuf>,
+  /// Initial heap size, e.g. 2G (defaults to the maximum heap size)
+  #[arg(long)]
+  min_memory: Option<HeapSize>,
+  /// Maximum heap size, e.g. 4G, or "auto" to size it from the available
+  /// memory
+  #[arg(long)]
+  max_memory: Option<HeapSetting>,
+  /// Built-in set of JVM flags to use
+  #[arg(long, value_enum)]
+  jvm_preset: Option<JvmPreset>,
+  /// Extra argument for the JVM (may be repeated)
+  #[arg(long = "jvm-arg", allow_hyphen_values = true)]
+  jvm_args: Vec<String>,
+  /// Extra argument for the server (may be repeated)
+  #[arg(long = "server-arg", allow_hyphen_values = true)]
+  server_args: Vec<String>,
+  /// Print the server build, Java runtime and command line that would be
+  /// used, without downloading, installing or starting anything
+  #[arg(long)]
+  dry_run: bool,
+  /// How to print the server's stderr
+  #[arg(long, value_enum, default_value_t = StderrMode::Merged)]
+  stderr: StderrMode,
+  /// Print version
+  #[arg(short = 'V', action = ArgAction::Version)]
+  print_version: Option<bool>,
+}
+
+const PROGRESS_BAR_WIDTH: u64 = 30;
+
+fn mebibytes(bytes: f64) -> f64 {
+  bytes / (1024.0 * 1024.0)
+}
+
+fn render_progress(progress: &DownloadProgress) {
+  let mut stderr = std::io::stderr().lock();
+  let received = mebibytes(progress.received as f64);
+  let rate = mebibytes(progress.bytes_per_second);
+  let _ = match progress.total {
+    Some(total) if total > 0 => {
+      let filled = (progress.received.min(total) * PROGRESS_BAR_WIDTH / total) as usize;
+      write!(
+        stderr,
+        "\rDownloading server [{}{}] {received:.1}/{:.1} MiB ({rate:.1} MiB/s)",
+        "#".repeat(filled),
+        " ".repeat(PROGRESS_BAR_WIDTH as usize - filled),
+        mebibytes(total as f64),
+      )
+    }
+    _ => write!(
+      stderr,
+      "\rDownloading server {received:.1} MiB ({rate:.1} MiB/s)"
+    ),
+  };
+  if Some(progress.received) == progress.total {
+    let _ = writeln!(stderr);
+  }
+  let _ = stderr.flush();
+}
+
+fn print_output(message: &OutputMessage, stderr_mode: StderrMode) {
+  match (message.source, stderr_mode) {
+    (OutputSource::Stdout, _) | (OutputSource::Stderr, StderrMode::Merged) => {
+      println!("{}", message.line)
+    }
+    (OutputSource::Stderr, StderrMode::Separate) => eprintln!("{}", message.line),
+    (OutputSource::Stderr, StderrMode::Colored) => println!("\x1b[31m{}\x1b[0m", message.line),
+  }
+}
+
+fn forward_console_input() -> Receiver<String> {
+  let (sender, receiver) = mpsc::channel(16);
+  thread::spawn(move || {
+    for line in stdin().lines() {
+      let Ok(line) = line else { break };
+      if sender.blocking_send(line).is_err() {
+        break;
+      }
+    }
+  });
+  receiver
+}
+
+fn handle_event(event: ServerEvent, stderr_mode: StderrMode) {
+  match event {
+    ServerEvent::DownloadProgress(progress) => render_progress(&progress),
+    ServerEvent::JavaSelected(runtime) => eprintln!("Using {runtime}"),
+    ServerEvent::Ready { after } => {
+      eprintln!("Server is ready after {:.1}s", after.as_secs_f64())
+    }
+    ServerEvent::Output(message) => print_output(&message, stderr_mode),
+    ServerEvent::Restarting {
+      attempt,
+      status,
+      crash_report,
+      delay,
+    } => {
+      eprintln!(
+        "Server exited with {status}, restarting in {}s (restart {attempt})",
+        delay.as_secs()
+      );
+      if let Some(crash_report) = crash_report {
+        eprintln!("Crash report: {}", crash_report.display());
+      }
+    }
+    ServerEvent::Stopping => eprintln!("Stopping server..."),
+    ServerEvent::Launched { .. }
+    | ServerEvent::Game(_)
+    | ServerEvent::OutputDropped { .. }
+    | ServerEvent::Exited { .. } => {}
+  }
+}
+
+/// Asks the user to agree to the Minecraft EULA if they have not already,
+/// provided there is a terminal to ask on.
+fn confirm_eula(directory: &Path) -> bool {
+  if !stdin().is_terminal() || run::eula::is_accepted(directory).unwrap_or(true) {
+    return false;
+  }
+  print!(
+    "Do you agree to the Minecraft EULA ({})? [y/N] ",
+    run::eula::EULA_URL
+  );
+  let _ = std::io::stdout().flush();
+  let mut answer = String::new();
+  if stdin().read_line(&mut answer).is_err() {
+    return false;
+  }
+  matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
+}
+
+fn report_error(error: &RunMinecraftError) {
+  eprintln!("Error: {error}");
+  if let RunMinecraftError::EulaNotAccepted = error {
+    eprintln!("Read the EULA and rerun with --accept-eula to agree to it");
+  }
+}
+
+/// Maps the server's exit to a process exit code, following the shell
+/// convention of `128 + signal` for processes killed by a signal.
+fn exit_code(outcome: &ServerOutcome) -> ExitCode {
+  match (outcome.exit_code, outcome.signal) {
+    (Some(0), _) if outcome.crash_report.is_some() => ExitCode::FAILURE,
+    (Some(code), _) => ExitCode::from(code as u8),
+    (None, Some(signal)) => ExitCode::from((128 + signal) as u8),
+    (None, None) => ExitCode::FAILURE,
+  }
+}
+
+async fn init_server(args: InitArgs) -> ExitCode {
+  let mut options = RunOptions {
+    flavor: args.server.flavor,
+    version: args.server.version_request(),
+    manifest_url: args.server.manifest_url,
+    accept_eula: args.accept_eula,
+    on_event: Some(Arc::new(|event| handle_event(event, StderrMode::Merged))),
+    launch: LaunchConfig {
+      java: args.java,
+      ..LaunchConfig::default()
+    },
+    ..RunOptions::default()
+  };
+  let settings = ServerSettings {
+    seed: args.seed,
+    gamemode: args.gamemode,
+    difficulty: args.difficulty,
+    port: args.port,
+    motd: args.motd,
+  };
+  if !options.accept_eula {
+    options.accept_eula = confirm_eula(&args.directory);
+  }
+
+  match run::init_minecraft_server(&args.directory, &options, &settings).await {
+    Ok(()) => {
+      println!("Server set up in \"{}\"", args.directory.display());
+      ExitCode::SUCCESS
+    }
+    Err(error) => {
+      report_error(&error);
+      ExitCode::FAILURE
+    }
+  }
+}
+
+fn configure_server(args: ConfigArgs) -> ExitCode {
+  let mut properties = match ServerProperties::load(&args.directory) {
+    Ok(properties) => properties,
+    Err(error) => {
+      eprintln!("Error: {error}");
+      return ExitCode::FAILURE;
+    }
+  };
+  match args.action {
+    ConfigAction::Get { key } => match properties.get(&key) {
+      Some(value) => println!("{value}"),
+      None => {
+        eprintln!("{key} is not set");
+        return ExitCode::FAILURE;
+      }
+    },
+    ConfigAction::Set { key, value } => {
+      if let Err(error) = properties.set(&key, &value) {
+        eprintln!("Error: {error}");
+        return ExitCode::FAILURE;
+      }
+      if let Err(error) = properties.save(&args.directory) {
+        eprintln!("Error: could not write server.properties: {error}");
+        return ExitCode::FAILURE;
+      }
+    }
+    ConfigAction::List => {
+      for (key, value) in properties.iter() {
+        println!("{key}={value}");
+      }
+    }
+  }
+  ExitCode::SUCCESS
+}
+
+/// Removes the `§` colour and style codes from server output.
+fn strip_formatting(text: &str) -> String {
+  let mut stripped = String::with_capacity(text.len());
+  let mut chars = text.chars();
+  while let Some(c) = chars.next() {
+    if c == '§' {
+      chars.next();
+    } else {
+      stripped.push(c);
+    }
+  }
+  stripped
+}
+
+async fn run_rcon(args: RconArgs) -> ExitCode {
+  let properties = match ServerProperties::load(&args.directory) {
+    Ok(properties) => properties,
+    Err(error) => {
+      eprintln!("Error: {error}");
+      return ExitCode::FAILURE;
+    }
+  };
+  if args.port.is_none() && !properties.enable_rcon() {
+    eprintln!("Warning: RCON is not enabled in server.properties (enable-rcon=true)");
+  }
+  let Some(password) = args.password.as_deref().or(properties.rcon_password()) else {
+    eprintln!("Error: no RCON password given and rcon.password is not set");
+    return ExitCode::FAILURE;
+  };
+  let host = args
+    .host
+    .as_deref()
+    .or(properties.server_ip())
+    .unwrap_or("localhost");
+  let port = args.port.unwrap_or(properties.rcon_port());
+
+  let mut client = match RconClient::connect((host, port), password).await {
+    Ok(client) => client,
+    Err(error) => {
+      eprintln!("Error: could not connect to {host}:{port}: {error}");
+      return ExitCode::FAILURE;
+    }
+  };
+  let mut commands = if args.command.is_empty() {
+    forward_console_input()
+  } else {
+    let (sender, receiver) = mpsc::channel(1);
+    let _ = sender.try_send(args.command.join(" "));
+    receiver
+  };
+  while let Some(command) = commands.recv().await {
+    match client.command(&command).await {
+      Ok(output) if output.is_empty() => {}
+      Ok(output) => println!("{}", strip_formatting(&output)),
+      Err(error) => {
+        eprintln!("Error: {error}");
+        return ExitCode::FAILURE;
+      }
+    }
+  }
+  ExitCode::SUCCESS
 }
 
-fn main() {
-  let directory = Args::parse().directory;
+/// Works out the address of the server to ask about itself, which is either
+/// given directly or read from a server directory, using `port` to pick the
+/// port for the protocol from its settings.
+fn server_address(
+  target: &str,
+  port: fn(&ServerProperties) -> u16,
+) -> Result<(String, u16), String> {
+  let directory = Path::new(target);
+  if directory.is_dir() {
+    let properties = ServerProperties::load(directory)
+      .map_err(|error| format!("could not read server.properties: {error}"))?;
+    let host = properties.server_ip().unwrap_or("localhost");
+    return Ok((host.to_string(), port(&properties)));
+  }
+  parse_address(target)
+}
+
+/// Splits `host:port` into its parts, where an IPv6 host has to be bracketed
+/// to be given a port.
+fn parse_address(target: &str) -> Result<(String, u16), String> {
+  let (host, port) = match target.strip_prefix('[') {
+    Some(rest) => {
+      let (host, rest) = rest
+        .split_once(']')
+        .ok_or_else(|| format!("missing \"]\" in address \"{target}\""))?;
+      match rest.strip_prefix(':') {
+        Some(port) => (host, Some(port)),
+        None if rest.is_empty() => (host, None),
+        None => return Err(format!("invalid address \"{target}\"")),
+      }
+    }
+    None => match target.split_once(':') {
+      // Any more colons make it a bare IPv6 address.
+      Some((host, port)) if !port.contains(':') => (host, Some(port)),
+      _ => (target, None),
+    },
+  };
+  let port = match port {
+    Some(port) => port
+      .parse()
+      .map_err(|_| format!("invalid port \"{port}\" in address \"{target}\""))?,
+    None => DEFAULT_SERVER_PORT,
+  };
+  Ok((host.to_string(), port))
+}
+
+fn print_status(status: &ServerStatus) {
+  println!("MOTD:     {}", strip_formatting(&status.motd));
+  match status.protocol {
+    Some(protocol) => println!("Version:  {} (protocol {protocol})", status.version),
+    None => println!("Version:  {}", status.version),
+  }
+  println!("Players:  {}/{}", status.online_players, status.max_players);
+  if !status.sample_players.is_empty() {
+    println!("Online:   {}", status.sample_players.join(", "));
+  }
+  println!("Latency:  {}ms", status.latency.as_millis());
+  if status.legacy {
+    println!("(answered the legacy pre-1.7 ping)");
+  }
+}
+
+async fn show_status(args: ServerAddressArgs) -> ExitCode {
+  let (host, port) = match server_address(&args.target, ServerProperties::server_port) {
+    Ok(address) => address,
+    Err(error) => {
+      eprintln!("Error: {error}");
+      return ExitCode::FAILURE;
+    }
+  };
+  match run::ping::ping(&host, port, Duration::from_secs(args.timeout)).await {
+    Ok(status) => {
+      print_status(&status);
+      ExitCode::SUCCESS
+    }
+    Err(error) => {
+      eprintln!("Error: could not get the status of {host}:{port}: {error}");
+      ExitCode::FAILURE
+    }
+  }
+}
+
+fn print_query(stats: &QueryStats) {
+  println!("MOTD:     {}", strip_formatting(&stats.motd));
+  println!("Version:  {}", stats.version);
+  if let Some(server_mod) = &stats.server_mod {
+    println!("Software: {server_mod}");
+  }
+  if !stats.plugins.is_empty() {
+    println!("Plugins:  {}", stats.plugins.join(", "));
+  }
+  println!("World:    {}", stats.map);
+  println!("Players:  {}/{}", stats.online_players, stats.max_players);
+  if !stats.players.is_empty() {
+    println!("Online:   {}", stats.players.join(", "));
+  }
+}
+
+async fn show_query(args: ServerAddressArgs) -> ExitCode {
+  let (host, port) = match server_address(&args.target, ServerProperties::query_port) {
+    Ok(address) => address,
+    Err(error) => {
+      eprintln!("Error: {error}");
+      return ExitCode::FAILURE;
+    }
+  };
+  match run::query::query(&host, port, Duration::from_secs(args.timeout)).await {
+    Ok(stats) => {
+      print_query(&stats);
+      ExitCode::SUCCESS
+    }
+    Err(error) => {
+      eprintln!("Error: could not query {host}:{port}: {error}");
+      ExitCode::FAILURE
+    }
+  }
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+  let args = Args::parse();
+  let stderr_mode = args.stderr;
+  match args.command {
+    Some(Command::Init(init)) => return init_server(init).await,
+    Some(Command::Config(config)) => return configure_server(config),
+    Some(Command::Rcon(rcon)) => return run_rcon(rcon).await,
+    Some(Command::Status(status)) => return show_status(status).await,
+    Some(Command::Query(query)) => return show_query(query).await,
+    None => {}
+  }
+
+  let mut options = RunOptions {
+    flavor: args.server.flavor,
+    version: args.server.version_request(),
+    manifest_url: args.server.manifest_url,
+    accept_eula: args.accept_eula,
+    on_event: Some(Arc::new(move |event| handle_event(event, stderr_mode))),
+    handle_signals: true,
+    shutdown_timeout: Duration::from_secs(args.shutdown_timeout),
+    ready_timeout: args.ready_timeout.map(Duration::from_secs),
+    restart_policy: RestartPolicy {
+      mode: args.restart,
+      max_restarts: args.max_restarts,
+      window: Duration::from_secs(args.restart_window),
+      backoff: Duration::from_secs(args.restart_backoff),
+      ..RestartPolicy::default()
+    },
+    launch: LaunchConfig {
+      java: args.java,
+      min_memory: args.min_memory,
+      max_memory: args.max_memory,
+      preset: args.jvm_preset,
+      jvm_args: args.jvm_args,
+      server_args: args.server_args,
+    },
+  };
+  let directory = args
+    .directory
+    .expect("Directory is required without a subcommand");
+
+  if args.list_versions {
+    match run::list_server_versions(&directory, &options).await {
+      Ok(versions) => versions.iter().for_each(|version| println!("{version}")),
+      Err(error) => {
+        report_error(&error);
+        return ExitCode::FAILURE;
+      }
+    }
+    return ExitCode::SUCCESS;
+  }
+
+  if args.dry_run {
+    match run::resolve_minecraft_server(&directory, &options).await {
+      Ok(plan) => {
+        let build = plan.build.build.map(|build| format!(" build {build}"));
+        eprintln!(
+          "Server: {:?} {}{}",
+          plan.flavor,
+          plan.build.version,
+          build.unwrap_or_default()
+        );
+        println!("{}", plan.command);
+      }
+      Err(error) => {
+        report_error(&error);
+        return ExitCode::FAILURE;
+      }
+    }
+    return ExitCode::SUCCESS;
+  }
+
+  if !options.accept_eula {
+    options.accept_eula = confirm_eula(&directory);
+  }
 
   println!("Running Minecraft from \"{}\"...", directory.display());
-  if let Err(error) = run::run_minecraft_server(&directory, stdout()) {
-    eprintln!("Error: {error}");
+  let outcome = match run::run_minecraft_server(
+    &directory,
+    &options,
+    forward_console_input(),
+    io::sink(),
+  )
+  .await
+  {
+    Ok(outcome) => outcome,
+    Err(error) => {
+      report_error(&error);
+      return ExitCode::FAILURE;
+    }
+  };
+  match outcome.shutdown {
+    Some(ShutdownMethod::Stopped) => println!("Server stopped gracefully"),
+    Some(ShutdownMethod::Terminated) => println!("Server did not stop in time and was terminated"),
+    Some(ShutdownMethod::Killed) => println!("Server did not stop in time and was killed"),
+    Some(ShutdownMethod::Forced) => println!("Server was forced to stop"),
+    None => {}
+  }
+  match (outcome.exit_code, outcome.signal) {
+    (Some(code), _) => println!(
+      "Server exited with code {code} after {}s",
+      outcome.duration.as_secs()
+    ),
+    (None, Some(signal)) => println!(
+      "Server was killed by signal {signal} after {}s",
+      outcome.duration.as_secs()
+    ),
+    (None, None) => {}
+  }
+  if let Some(crash_report) = &outcome.crash_report {
+    println!("Crash report: {}", crash_report.display());
+  }
+
+  exit_code(&outcome)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn parses_addresses() {
+    let address = |host: &str, port| Ok((host.to_string(), port));
+    assert_eq!(parse_address("example.com"), address("example.com", 25565));
+    assert_eq!(
+      parse_address("example.com:25570"),
+      address("example.com", 25570)
+    );
+    assert_eq!(parse_address("::1"), address("::1", 25565));
+    assert_eq!(parse_address("fe80::1:2"), address("fe80::1:2", 25565));
+    assert_eq!(parse_address("[::1]"), address("::1", 25565));
+    assert_eq!(parse_address("[::1]:25570"), address("::1", 25570));
+  }
+
+  #[test]
+  fn rejects_bad_addresses() {
+    assert!(parse_address("example.com:abc").is_err());
+    assert!(parse_address("example.com:").is_err());
+    assert!(parse_address("example.com:70000").is_err());
+    assert!(parse_address("[::1]:abc").is_err());
+    assert!(parse_address("[::1").is_err());
+    assert!(parse_address("[::1]25570").is_err());
   }
 }
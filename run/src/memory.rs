@@ -0,0 +1,82 @@
+use crate::launch::{HeapSize, GIBIBYTE, KIBIBYTE, MEBIBYTE};
+use std::fs;
+use std::path::PathBuf;
+
+/// Least memory left to the OS and to the JVM's own non-heap allocations.
+const MIN_HEADROOM: u64 = GIBIBYTE;
+/// Smallest heap chosen automatically, even on machines with little memory.
+const MIN_AUTO_HEAP: u64 = 512 * MEBIBYTE;
+
+/// Reads the total physical memory from `/proc/meminfo`.
+fn total_memory() -> Option<u64> {
+  let meminfo = fs::read_to_string("/proc/meminfo").ok()?;
+  let line = meminfo.lines().find(|line| line.starts_with("MemTotal:"))?;
+  let kibibytes = line.split_whitespace().nth(1)?.parse::<u64>().ok()?;
+  Some(kibibytes * KIBIBYTE)
+}
+
+/// Lists the files that may hold the memory limit of the cgroup this process
+/// runs in, covering both cgroup v1 and v2 hierarchies.
+fn cgroup_limit_files() -> Vec<PathBuf> {
+  let mut files = vec![
+    PathBuf::from("/sys/fs/cgroup/memory.max"),
+    PathBuf::from("/sys/fs/cgroup/memory/memory.limit_in_bytes"),
+  ];
+  let Ok(cgroups) = fs::read_to_string("/proc/self/cgroup") else {
+    return files;
+  };
+  // Lines look like `0::/path` for v2 and `4:memory:/path` for v1.
+  for line in cgroups.lines() {
+    let mut fields = line.splitn(3, ':');
+    let (Some(_), Some(controllers), Some(path)) = (fields.next(), fields.next(), fields.next())
+    else {
+      continue;
+    };
+    let path = path.trim_start_matches('/');
+    if controllers.is_empty() {
+      files.push(
+        PathBuf::from("/sys/fs/cgroup")
+          .join(path)
+          .join("memory.max"),
+      );
+    } else if controllers
+      .split(',')
+      .any(|controller| controller == "memory")
+    {
+      files.push(
+        PathBuf::from("/sys/fs/cgroup/memory")
+          .join(path)
+          .join("memory.limit_in_bytes"),
+      );
+    }
+  }
+  files
+}
+
+/// Finds the tightest cgroup memory limit applying to this process, if any.
+fn cgroup_limit() -> Option<u64> {
+  cgroup_limit_files()
+    .iter()
+    .filter_map(|file| fs::read_to_string(file).ok())
+    // Unlimited cgroups read `max` in v2 and a huge number in v1.
+    .filter_map(|limit| limit.trim().parse::<u64>().ok())
+    .min()
+}
+
+/// Works out how much memory the server may use, taking container limits
+/// into account. Only supported on Linux.
+pub fn available_memory() -> Option<u64> {
+  match (total_memory(), cgroup_limit()) {
+    (Some(total), Some(limit)) => Some(total.min(limit)),
+    (total, limit) => total.or(limit),
+  }
+}
+
+/// Chooses a heap size for a server that has `memory` bytes to itself,
+/// leaving a quarter of it, and at least [`MIN_HEADROOM`], to everything else.
+pub fn heap_size_for(memory: u64) -> HeapSize {
+  let headroom = (memory / 4).max(MIN_HEADROOM);
+  let heap = memory.saturating_sub(headroom).max(MIN_AUTO_HEAP);
+  // Whole mebibytes keep the JVM flags readable.
+  HeapSize(heap / MEBIBYTE * MEBIBYTE)
+}
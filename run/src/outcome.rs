@@ -0,0 +1,50 @@
+use crate::shutdown::ShutdownMethod;
+use std::path::PathBuf;
+use std::process::ExitStatus;
+use std::time::Duration;
+
+/// How a run of the server ended.
+#[derive(Debug, Clone)]
+pub struct ServerOutcome {
+  /// Exit code of the last server process, if it exited normally
+  pub exit_code: Option<i32>,
+  /// Signal which terminated the last server process, if any
+  pub signal: Option<i32>,
+  /// How long the last server process ran for
+  pub duration: Duration,
+  /// Crash report written by the last server process, if any
+  pub crash_report: Option<PathBuf>,
+  /// How the runner stopped the server, if it was asked to
+  pub shutdown: Option<ShutdownMethod>,
+  /// Number of times the server was restarted
+  pub restarts: u32,
+}
+
+impl ServerOutcome {
+  pub(crate) fn new(
+    status: ExitStatus,
+    duration: Duration,
+    crash_report: Option<PathBuf>,
+    shutdown: Option<ShutdownMethod>,
+    restarts: u32,
+  ) -> Self {
+    #[cfg(unix)]
+    let signal = std::os::unix::process::ExitStatusExt::signal(&status);
+    #[cfg(not(unix))]
+    let signal = None;
+
+    Self {
+      exit_code: status.code(),
+      signal,
+      duration,
+      crash_report,
+      shutdown,
+      restarts,
+    }
+  }
+
+  /// Whether the server exited cleanly without leaving a crash report.
+  pub fn success(&self) -> bool {
+    self.exit_code == Some(0) && self.crash_report.is_none()
+  }
+}
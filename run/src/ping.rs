@@ -0,0 +1,472 @@
+use serde::Deserialize;
+use serde_json::Value;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::time;
+
+/// Protocol version sent in the handshake, which by convention tells the
+/// server the client is only finding out which version it runs.
+const ANY_PROTOCOL: i32 = -1;
+const STATUS_STATE: i32 = 1;
+const HANDSHAKE_ID: i32 = 0x00;
+const STATUS_REQUEST_ID: i32 = 0x00;
+const PING_ID: i32 = 0x01;
+/// Largest packet the protocol allows, since lengths are at most three byte
+/// VarInts.
+const MAX_PACKET_LENGTH: usize = (1 << 21) - 1;
+
+const LEGACY_PING: [u8; 2] = [0xFE, 0x01];
+const LEGACY_KICK: u8 = 0xFF;
+
+#[derive(Debug, Error)]
+pub enum PingError {
+  #[error("error in communicating with the server")]
+  IoError(#[from] std::io::Error),
+  #[error("server did not answer in time")]
+  Timeout,
+  #[error("server sent a malformed status response")]
+  MalformedResponse,
+  #[error("server sent an invalid status response: {0}")]
+  InvalidStatus(#[from] serde_json::Error),
+}
+
+/// What a server reports about itself in the multiplayer server list.
+#[derive(Debug, Clone)]
+pub struct ServerStatus {
+  /// Name of the version the server runs, such as `1.20.1` or `Paper 1.20.1`
+  pub version: String,
+  /// Protocol version number, not reported by the oldest servers
+  pub protocol: Option<i32>,
+  /// Message of the day, as plain text that may contain `§` formatting codes
+  pub motd: String,
+  pub online_players: u32,
+  pub max_players: u32,
+  /// Names of some of the players online, if the server shares them
+  pub sample_players: Vec<String>,
+  pub latency: Duration,
+  /// Whether the server only answered the pre-1.7 ping
+  pub legacy: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct StatusVersion {
+  name: String,
+  protocol: i32,
+}
+
+#[derive(Debug, Deserialize)]
+struct SamplePlayer {
+  name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct StatusPlayers {
+  max: u32,
+  online: u32,
+  #[serde(default)]
+  sample: Vec<SamplePlayer>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StatusResponse {
+  version: StatusVersion,
+  players: Option<StatusPlayers>,
+  #[serde(default)]
+  description: Value,
+}
+
+/// Asks the server at `host` and `port` for its status, falling back to the
+/// ping used before Minecraft 1.7 if the server does not understand the
+/// current one.
+pub async fn ping(host: &str, port: u16, timeout: Duration) -> Result<ServerStatus, PingError> {
+  let modern = time::timeout(timeout, ping_modern(host, port)).await;
+  match modern {
+    Ok(Ok(status)) => Ok(status),
+    // Old servers hang up on or ignore the current handshake.
+    Ok(Err(PingError::IoError(_) | PingError::MalformedResponse)) | Err(_) => {
+      time::timeout(timeout, ping_legacy(host, port))
+        .await
+        .map_err(|_| PingError::Timeout)?
+    }
+    Ok(Err(error)) => Err(error),
+  }
+}
+
+/// Pings the server at `host` and `port` every `interval` until it answers,
+/// which it only does once it has finished starting.
+pub async fn wait_until_up(host: &str, port: u16, interval: Duration) -> ServerStatus {
+  loop {
+    time::sleep(interval).await;
+    if let Ok(status) = ping(host, port, interval).await {
+      return status;
+    }
+  }
+}
+
+async fn ping_modern(host: &str, port: u16) -> Result<ServerStatus, PingError> {
+  let mut stream = TcpStream::connect((host, port)).await?;
+
+  let mut handshake = Vec::new();
+  write_varint(&mut handshake, HANDSHAKE_ID);
+  write_varint(&mut handshake, ANY_PROTOCOL);
+  write_string(&mut handshake, host);
+  handshake.extend(port.to_be_bytes());
+  write_varint(&mut handshake, STATUS_STATE);
+  let mut request = frame(&handshake);
+  request.extend(frame(&varint(STATUS_REQUEST_ID)));
+  stream.write_all(&request).await?;
+
+  let response = read_packet(&mut stream).await?;
+  let response = &mut response.as_slice();
+  if read_varint_from(response) != Some(STATUS_REQUEST_ID) {
+    return Err(PingError::MalformedResponse);
+  }
+  let length = read_varint_from(response).ok_or(PingError::MalformedResponse)?;
+  let json = usize::try_from(length)
+    .ok()
+    .and_then(|length| response.get(..length))
+    .ok_or(PingError::MalformedResponse)?;
+  let status: StatusResponse = serde_json::from_slice(json)?;
+
+  let payload = now_millis();
+  let mut ping = varint(PING_ID);
+  ping.extend(payload.to_be_bytes());
+  let sent = Instant::now();
+  stream.write_all(&frame(&ping)).await?;
+  let pong = read_packet(&mut stream).await?;
+  let latency = sent.elapsed();
+  let pong = &mut pong.as_slice();
+  if read_varint_from(pong) != Some(PING_ID) || *pong != payload.to_be_bytes() {
+    return Err(PingError::MalformedResponse);
+  }
+
+  let players = status.players.unwrap_or(StatusPlayers {
+    max: 0,
+    online: 0,
+    sample: Vec::new(),
+  });
+  Ok(ServerStatus {
+    version: status.version.name,
+    protocol: Some(status.version.protocol),
+    motd: chat_text(&status.description),
+    online_players: players.online,
+    max_players: players.max,
+    sample_players: players
+      .sample
+      .into_iter()
+      .map(|player| player.name)
+      .collect(),
+    latency,
+    legacy: false,
+  })
+}
+
+/// Pings the way clients before 1.7 do, which the server answers by kicking
+/// the client with its status as the reason.
+async fn ping_legacy(host: &str, port: u16) -> Result<ServerStatus, PingError> {
+  let mut stream = TcpStream::connect((host, port)).await?;
+  let sent = Instant::now();
+  stream.write_all(&LEGACY_PING).await?;
+  if stream.read_u8().await? != LEGACY_KICK {
+    return Err(PingError::MalformedResponse);
+  }
+  let latency = sent.elapsed();
+  let length = stream.read_u16().await?;
+  let mut reason = vec![0; usize::from(length) * 2];
+  stream.read_exact(&mut reason).await?;
+  let units: Vec<u16> = reason
+    .chunks_exact(2)
+    .map(|unit| u16::from_be_bytes([unit[0], unit[1]]))
+    .collect();
+  let reason = String::from_utf16_lossy(&units);
+
+  // 1.4 and later send `§1`, protocol, version, MOTD and player counts
+  // separated by nulls, while older servers send `MOTD§online§max`.
+  let fields: Vec<&str> = match reason.strip_prefix("§1\0") {
+    Some(fields) => fields.split('\0').collect(),
+    None => {
+      let mut fields: Vec<&str> = reason.rsplitn(3, '§').collect();
+      fields.reverse();
+      [vec![""; 2], fields].concat()
+    }
+  };
+  let [protocol, version, motd, online, max] = fields[..] else {
+    return Err(PingError::MalformedResponse);
+  };
+  Ok(ServerStatus {
+    version: version.to_string(),
+    protocol: protocol.parse().ok(),
+    motd: motd.to_string(),
+    online_players: online.parse().map_err(|_| PingError::MalformedResponse)?,
+    max_players: max.parse().map_err(|_| PingError::MalformedResponse)?,
+    sample_players: Vec::new(),
+    latency,
+    legacy: true,
+  })
+}
+
+fn now_millis() -> i64 {
+  std::time::SystemTime::now()
+    .duration_since(std::time::UNIX_EPOCH)
+    .map_or(0, |elapsed| elapsed.as_millis() as i64)
+}
+
+/// Flattens a chat component, or a plain string, into its text.
+fn chat_text(component: &Value) -> String {
+  match component {
+    Value::String(text) => text.clone(),
+    Value::Array(components) => components.iter().map(chat_text).collect(),
+    Value::Object(fields) => {
+      let mut text = fields.get("text").map(chat_text).unwrap_or_default();
+      if let Some(Value::Array(extra)) = fields.get("extra") {
+        text.extend(extra.iter().map(chat_text));
+      }
+      text
+    }
+    _ => String::new(),
+  }
+}
+
+fn write_varint(buffer: &mut Vec<u8>, value: i32) {
+  let mut value = value as u32;
+  loop {
+    let byte = (value & 0x7F) as u8;
+    value >>= 7;
+    if value == 0 {
+      buffer.push(byte);
+      return;
+    }
+    buffer.push(byte | 0x80);
+  }
+}
+
+fn varint(value: i32) -> Vec<u8> {
+  let mut buffer = Vec::with_capacity(5);
+  write_varint(&mut buffer, value);
+  buffer
+}
+
+fn write_string(buffer: &mut Vec<u8>, text: &str) {
+  write_varint(buffer, text.len() as i32);
+  buffer.extend(text.as_bytes());
+}
+
+/// Prefixes a packet with its length.
+fn frame(packet: &[u8]) -> Vec<u8> {
+  let mut framed = varint(packet.len() as i32);
+  framed.extend(packet);
+  framed
+}
+
+fn read_varint_from(bytes: &mut &[u8]) -> Option<i32> {
+  let mut value = 0u32;
+  for shift in (0..35).step_by(7) {
+    let (&byte, rest) = bytes.split_first()?;
+    *bytes = rest;
+    value |= u32::from(byte & 0x7F) << shift;
+    if byte & 0x80 == 0 {
+      return Some(value as i32);
+    }
+  }
+  None
+}
+
+async fn read_varint(reader: &mut (impl AsyncRead + Unpin)) -> Result<i32, PingError> {
+  let mut value = 0u32;
+  for shift in (0..35).step_by(7) {
+    let byte = reader.read_u8().await?;
+    value |= u32::from(byte & 0x7F) << shift;
+    if byte & 0x80 == 0 {
+      return Ok(value as i32);
+    }
+  }
+  Err(PingError::MalformedResponse)
+}
+
+async fn read_packet(reader: &mut (impl AsyncRead + Unpin)) -> Result<Vec<u8>, PingError> {
+  let length = usize::try_from(read_varint(reader).await?)
+    .ok()
+    .filter(|length| *length <= MAX_PACKET_LENGTH)
+    .ok_or(PingError::MalformedResponse)?;
+  let mut packet = vec![0; length];
+  reader.read_exact(&mut packet).await?;
+  Ok(packet)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use tokio::net::TcpListener;
+
+  const STATUS_JSON: &str = r#"{
+    "version": {"name": "Paper 1.20.1", "protocol": 763},
+    "players": {"max": 20, "online": 2, "sample": [{"name": "Steve", "id": "8667ba71-b85a-4004-af54-457a9734eed7"}]},
+    "description": {"text": "A ", "extra": [{"text": "Paper", "color": "gold"}, " server"]}
+  }"#;
+  const PONG_DELAY: Duration = Duration::from_millis(20);
+
+  #[derive(Clone)]
+  enum FakeServer {
+    Modern,
+    /// Hangs up on the current handshake and kicks legacy pings with this
+    /// reason
+    Legacy(&'static str),
+  }
+
+  /// Starts a fake server that answers every connection as `server`.
+  async fn serve(server: FakeServer) -> u16 {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+      loop {
+        let (stream, _) = listener.accept().await.unwrap();
+        tokio::spawn(answer(stream, server.clone(), port));
+      }
+    });
+    port
+  }
+
+  async fn answer(mut stream: TcpStream, server: FakeServer, port: u16) {
+    match server {
+      FakeServer::Modern => {
+        let handshake = read_packet(&mut stream).await.unwrap();
+        let handshake = &mut handshake.as_slice();
+        assert_eq!(read_varint_from(handshake), Some(HANDSHAKE_ID));
+        assert_eq!(read_varint_from(handshake), Some(ANY_PROTOCOL));
+        assert_eq!(read_varint_from(handshake), Some(9));
+        let (host, rest) = handshake.split_at(9);
+        assert_eq!(host, b"127.0.0.1");
+        assert_eq!(
+          rest,
+          [&port.to_be_bytes()[..], &[STATUS_STATE as u8]].concat()
+        );
+        assert_eq!(
+          read_packet(&mut stream).await.unwrap(),
+          varint(STATUS_REQUEST_ID)
+        );
+
+        let mut response = varint(STATUS_REQUEST_ID);
+        write_string(&mut response, STATUS_JSON);
+        stream.write_all(&frame(&response)).await.unwrap();
+
+        let ping = read_packet(&mut stream).await.unwrap();
+        assert_eq!(ping.len(), 9);
+        assert_eq!(ping[0], PING_ID as u8);
+        time::sleep(PONG_DELAY).await;
+        stream.write_all(&frame(&ping)).await.unwrap();
+      }
+      FakeServer::Legacy(reason) => {
+        let mut request = [0; 2];
+        stream.read_exact(&mut request).await.unwrap();
+        if request != LEGACY_PING {
+          return;
+        }
+        let units: Vec<u16> = reason.encode_utf16().collect();
+        let mut kick = vec![LEGACY_KICK];
+        kick.extend((units.len() as u16).to_be_bytes());
+        kick.extend(units.iter().flat_map(|unit| unit.to_be_bytes()));
+        stream.write_all(&kick).await.unwrap();
+      }
+    }
+  }
+
+  #[tokio::test]
+  async fn pings_modern_server() {
+    let port = serve(FakeServer::Modern).await;
+    let status = ping("127.0.0.1", port, Duration::from_secs(5))
+      .await
+      .unwrap();
+    assert_eq!(status.version, "Paper 1.20.1");
+    assert_eq!(status.protocol, Some(763));
+    assert_eq!(status.motd, "A Paper server");
+    assert_eq!(status.online_players, 2);
+    assert_eq!(status.max_players, 20);
+    assert_eq!(status.sample_players, ["Steve"]);
+    assert!(status.latency >= PONG_DELAY);
+    assert!(!status.legacy);
+  }
+
+  #[tokio::test]
+  async fn falls_back_to_legacy_ping() {
+    let port = serve(FakeServer::Legacy(
+      "§1\u{0}61\u{0}1.5.2\u{0}Old §aserver\u{0}3\u{0}10",
+    ))
+    .await;
+    let status = ping("127.0.0.1", port, Duration::from_secs(5))
+      .await
+      .unwrap();
+    assert_eq!(status.version, "1.5.2");
+    assert_eq!(status.protocol, Some(61));
+    assert_eq!(status.motd, "Old §aserver");
+    assert_eq!(status.online_players, 3);
+    assert_eq!(status.max_players, 10);
+    assert!(status.legacy);
+  }
+
+  #[tokio::test]
+  async fn reads_beta_legacy_status() {
+    let port = serve(FakeServer::Legacy("Beta § server§1§8")).await;
+    let status = ping("127.0.0.1", port, Duration::from_secs(5))
+      .await
+      .unwrap();
+    assert_eq!(status.version, "");
+    assert_eq!(status.protocol, None);
+    assert_eq!(status.motd, "Beta § server");
+    assert_eq!(status.online_players, 1);
+    assert_eq!(status.max_players, 8);
+    assert!(status.legacy);
+  }
+
+  #[tokio::test]
+  async fn rejects_malformed_legacy_status() {
+    let port = serve(FakeServer::Legacy("no counts here")).await;
+    let result = ping("127.0.0.1", port, Duration::from_secs(5)).await;
+    assert!(matches!(result, Err(PingError::MalformedResponse)));
+  }
+
+  #[test]
+  fn flattens_chat_components() {
+    let component = serde_json::json!([
+      "",
+      {"text": "Hello ", "bold": true, "extra": [{"text": "world"}, "!"]},
+    ]);
+    assert_eq!(chat_text(&component), "Hello world!");
+    assert_eq!(chat_text(&Value::Null), "");
+  }
+
+  #[test]
+  fn reads_varints_at_limits() {
+    let cases: [(&[u8], i32); 6] = [
+      (&[0x00], 0),
+      (&[0x7F], 127),
+      (&[0x80, 0x01], 128),
+      (&[0xFF, 0xFF, 0x7F], (1 << 21) - 1),
+      (&[0xFF, 0xFF, 0xFF, 0xFF, 0x07], i32::MAX),
+      (&[0xFF, 0xFF, 0xFF, 0xFF, 0x0F], -1),
+    ];
+    for (bytes, value) in cases {
+      let mut rest = bytes;
+      assert_eq!(read_varint_from(&mut rest), Some(value));
+      assert!(rest.is_empty());
+      assert_eq!(varint(value), bytes);
+    }
+    assert_eq!(varint(i32::MIN), [0x80, 0x80, 0x80, 0x80, 0x08]);
+  }
+
+  #[test]
+  fn rejects_bad_varints() {
+    // Too long, and cut off before the last byte
+    assert_eq!(read_varint_from(&mut &[0x80; 6][..]), None);
+    assert_eq!(read_varint_from(&mut &[0x80, 0x80][..]), None);
+    assert_eq!(read_varint_from(&mut &[][..]), None);
+  }
+
+  #[test]
+  fn stops_reading_varint_at_end() {
+    let mut bytes = &[0x80, 0x01, 0x2A][..];
+    assert_eq!(read_varint_from(&mut bytes), Some(128));
+    assert_eq!(bytes, [0x2A]);
+  }
+}
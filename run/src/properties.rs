@@ -0,0 +1,878 @@
+use clap::ValueEnum;
+use std::fmt::{self, Display, Formatter, Write};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+use std::str::FromStr;
+use thiserror::Error;
+
+pub const PROPERTIES_FILENAME: &str = "server.properties";
+
+const DEFAULT_LEVEL_NAME: &str = "world";
+const DEFAULT_MOTD: &str = "A Minecraft Server";
+pub const DEFAULT_SERVER_PORT: u16 = 25565;
+pub const DEFAULT_RCON_PORT: u16 = 25575;
+
+#[derive(Debug, Error)]
+#[error("invalid value \"{value}\" for {key}, expected {expected}")]
+pub struct InvalidPropertyError {
+  pub key: String,
+  pub value: String,
+  pub expected: String,
+}
+
+#[derive(Debug, Error)]
+#[error("invalid value \"{0}\"")]
+pub struct ParsePropertyError(String);
+
+/// What a known setting accepts, so that mistakes are caught before the
+/// server quietly falls back to the default.
+#[derive(Debug, Clone, Copy)]
+enum ValueKind {
+  Boolean,
+  Integer { min: i64, max: i64 },
+  GameMode,
+  Difficulty,
+  Text,
+}
+
+const PORT: ValueKind = ValueKind::Integer { min: 1, max: 65535 };
+const DISTANCE: ValueKind = ValueKind::Integer { min: 3, max: 32 };
+const COUNT: ValueKind = ValueKind::Integer {
+  min: 0,
+  max: i32::MAX as i64,
+};
+
+const KNOWN_PROPERTIES: &[(&str, ValueKind)] = &[
+  ("allow-flight", ValueKind::Boolean),
+  ("difficulty", ValueKind::Difficulty),
+  ("enable-command-block", ValueKind::Boolean),
+  ("enable-query", ValueKind::Boolean),
+  ("enable-rcon", ValueKind::Boolean),
+  ("gamemode", ValueKind::GameMode),
+  ("hardcore", ValueKind::Boolean),
+  ("level-name", ValueKind::Text),
+  ("level-seed", ValueKind::Text),
+  ("max-players", COUNT),
+  ("motd", ValueKind::Text),
+  ("online-mode", ValueKind::Boolean),
+  ("pvp", ValueKind::Boolean),
+  ("query.port", PORT),
+  ("rcon.password", ValueKind::Text),
+  ("rcon.port", PORT),
+  ("server-port", PORT),
+  ("simulation-distance", DISTANCE),
+  ("spawn-protection", COUNT),
+  ("view-distance", DISTANCE),
+  ("white-list", ValueKind::Boolean),
+];
+
+impl ValueKind {
+  fn of(key: &str) -> Self {
+    KNOWN_PROPERTIES
+      .iter()
+      .find(|(known, _)| *known == key)
+      .map_or(Self::Text, |(_, kind)| *kind)
+  }
+
+  fn accepts(self, value: &str) -> bool {
+    match self {
+      Self::Boolean => value.eq_ignore_ascii_case("true") || value.eq_ignore_ascii_case("false"),
+      Self::Integer { min, max } => value
+        .parse::<i64>()
+        .is_ok_and(|value| (min..=max).contains(&value)),
+      Self::GameMode => value.parse::<GameMode>().is_ok(),
+      Self::Difficulty => value.parse::<Difficulty>().is_ok(),
+      Self::Text => true,
+    }
+  }
+
+  fn expected(self) -> String {
+    match self {
+      Self::Boolean => "true or false".to_string(),
+      Self::Integer { min, max } => format!("a whole number from {min} to {max}"),
+      Self::GameMode => "survival, creative, adventure or spectator".to_string(),
+      Self::Difficulty => "peaceful, easy, normal or hard".to_string(),
+      Self::Text => "text".to_string(),
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum GameMode {
+  Survival,
+  Creative,
+  Adventure,
+  Spectator,
+}
+
+impl GameMode {
+  pub fn as_str(self) -> &'static str {
+    match self {
+      Self::Survival => "survival",
+      Self::Creative => "creative",
+      Self::Adventure => "adventure",
+      Self::Spectator => "spectator",
+    }
+  }
+}
+
+impl FromStr for GameMode {
+  type Err = ParsePropertyError;
+
+  /// Parses a game mode by name, or by the number older servers use.
+  fn from_str(mode: &str) -> Result<Self, Self::Err> {
+    match mode.to_ascii_lowercase().as_str() {
+      "survival" | "0" => Ok(Self::Survival),
+      "creative" | "1" => Ok(Self::Creative),
+      "adventure" | "2" => Ok(Self::Adventure),
+      "spectator" | "3" => Ok(Self::Spectator),
+      _ => Err(ParsePropertyError(mode.to_string())),
+    }
+  }
+}
+
+impl Display for GameMode {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    f.write_str(self.as_str())
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Difficulty {
+  Peaceful,
+  Easy,
+  Normal,
+  Hard,
+}
+
+impl Difficulty {
+  pub fn as_str(self) -> &'static str {
+    match self {
+      Self::Peaceful => "peaceful",
+      Self::Easy => "easy",
+      Self::Normal => "normal",
+      Self::Hard => "hard",
+    }
+  }
+}
+
+impl FromStr for Difficulty {
+  type Err = ParsePropertyError;
+
+  /// Parses a difficulty by name, or by the number older servers use.
+  fn from_str(difficulty: &str) -> Result<Self, Self::Err> {
+    match difficulty.to_ascii_lowercase().as_str() {
+      "peaceful" | "0" => Ok(Self::Peaceful),
+      "easy" | "1" => Ok(Self::Easy),
+      "normal" | "2" => Ok(Self::Normal),
+      "hard" | "3" => Ok(Self::Hard),
+      _ => Err(ParsePropertyError(difficulty.to_string())),
+    }
+  }
+}
+
+impl Display for Difficulty {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    f.write_str(self.as_str())
+  }
+}
+
+/// A `server.properties` file, keeping its comments and layout so that it can
+/// be written back with only the changed settings touched.
+#[derive(Debug, Clone, Default)]
+pub struct ServerProperties {
+  lines: Vec<PropertyLine>,
+  encoding: Encoding,
+  line_ending: LineEnding,
+}
+
+/// Character encoding of a properties file, kept so that lines which are not
+/// changed are written back byte for byte.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum Encoding {
+  #[default]
+  Utf8,
+  /// ISO-8859-1, written by older servers, which maps each byte to the code
+  /// point of the same value
+  Latin1,
+}
+
+/// Line terminator used for added and changed settings, following the rest
+/// of the file.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum LineEnding {
+  #[default]
+  Lf,
+  CrLf,
+}
+
+impl LineEnding {
+  fn as_str(self) -> &'static str {
+    match self {
+      Self::Lf => "\n",
+      Self::CrLf => "\r\n",
+    }
+  }
+}
+
+#[derive(Debug, Clone)]
+enum PropertyLine {
+  /// A comment or blank line, kept verbatim along with its line terminator
+  Other(String),
+  /// A setting, along with its original text, which may span several lines
+  /// and keeps their terminators, unless it has been changed
+  Entry {
+    key: String,
+    value: String,
+    raw: Option<String>,
+  },
+}
+
+impl ServerProperties {
+  /// Reads `server.properties` from the server directory, which is empty if
+  /// the file does not exist yet.
+  pub fn load(directory: &Path) -> std::io::Result<Self> {
+    let bytes = match fs::read(directory.join(PROPERTIES_FILENAME)) {
+      Ok(bytes) => bytes,
+      Err(error) if error.kind() == ErrorKind::NotFound => return Ok(Self::default()),
+      Err(error) => return Err(error),
+    };
+    Ok(match String::from_utf8(bytes) {
+      Ok(contents) => Self::parse(&contents),
+      Err(error) => {
+        let contents: String = error.into_bytes().into_iter().map(char::from).collect();
+        Self {
+          encoding: Encoding::Latin1,
+          ..Self::parse(&contents)
+        }
+      }
+    })
+  }
+
+  /// Writes `server.properties` to the server directory, in the encoding it
+  /// was read in.
+  pub fn save(&self, directory: &Path) -> std::io::Result<()> {
+    let contents = self.to_string();
+    let bytes = match self.encoding {
+      Encoding::Utf8 => contents.into_bytes(),
+      // Changed settings are escaped down to ASCII, so everything else came
+      // from the file and fits in a byte.
+      Encoding::Latin1 => contents
+        .chars()
+        .map(|c| u8::try_from(c).unwrap_or(b'?'))
+        .collect(),
+    };
+    fs::write(directory.join(PROPERTIES_FILENAME), bytes)
+  }
+
+  /// Parses the contents of a properties file, following the rules of Java's
+  /// `Properties.load`.
+  pub fn parse(contents: &str) -> Self {
+    let mut lines = Vec::new();
+    let mut physical_lines = contents.split_inclusive('\n');
+    while let Some(line) = physical_lines.next() {
+      let trimmed = trim_start(strip_line_ending(line));
+      if trimmed.is_empty() || trimmed.starts_with(['#', '!']) {
+        lines.push(PropertyLine::Other(line.to_string()));
+        continue;
+      }
+
+      let mut raw = line.to_string();
+      let mut logical = trimmed.to_string();
+      while ends_with_continuation(&logical) {
+        logical.pop();
+        let Some(next) = physical_lines.next() else {
+          break;
+        };
+        raw.push_str(next);
+        logical.push_str(trim_start(strip_line_ending(next)));
+      }
+      let (key, value) = split_entry(&logical);
+      lines.push(PropertyLine::Entry {
+        key,
+        value,
+        raw: Some(raw),
+      });
+    }
+    Self {
+      lines,
+      encoding: Encoding::Utf8,
+      line_ending: if contents.contains("\r\n") {
+        LineEnding::CrLf
+      } else {
+        LineEnding::Lf
+      },
+    }
+  }
+
+  /// Gets the value of `key`, which is the last one given if it is set more
+  /// than once.
+  pub fn get(&self, key: &str) -> Option<&str> {
+    self
+      .entries()
+      .rev()
+      .find(|(entry_key, _)| *entry_key == key)
+      .map(|(_, value)| value)
+  }
+
+  /// Lists the settings in the order they appear in the file, skipping any
+  /// that are overridden further down.
+  pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+    self
+      .entries()
+      .enumerate()
+      .filter(|(index, (key, _))| {
+        self
+          .entries()
+          .skip(index + 1)
+          .all(|(later, _)| later != *key)
+      })
+      .map(|(_, entry)| entry)
+  }
+
+  /// Changes `key` to `value`, adding it to the end of the file if it is not
+  /// set yet. Values for known settings are checked before being stored.
+  pub fn set(&mut self, key: &str, value: &str) -> Result<(), InvalidPropertyError> {
+    let kind = ValueKind::of(key);
+    if !kind.accepts(value) {
+      return Err(InvalidPropertyError {
+        key: key.to_string(),
+        value: value.to_string(),
+        expected: kind.expected(),
+      });
+    }
+    self.set_unchecked(key, value.to_string());
+    Ok(())
+  }
+
+  fn set_unchecked(&mut self, key: &str, value: String) {
+    let entry = self.lines.iter_mut().rev().find_map(|line| match line {
+      PropertyLine::Entry {
+        key: entry_key,
+        value,
+        raw,
+      } if entry_key == key => Some((value, raw)),
+      _ => None,
+    });
+    match entry {
+      Some((entry_value, raw)) => {
+        if *entry_value != value {
+          *entry_value = value;
+          *raw = None;
+        }
+      }
+      None => self.lines.push(PropertyLine::Entry {
+        key: key.to_string(),
+        value,
+        raw: None,
+      }),
+    }
+  }
+
+  fn entries(&self) -> impl DoubleEndedIterator<Item = (&str, &str)> {
+    self.lines.iter().filter_map(|line| match line {
+      PropertyLine::Entry { key, value, .. } => Some((key.as_str(), value.as_str())),
+      PropertyLine::Other(_) => None,
+    })
+  }
+
+  fn parsed<T: FromStr>(&self, key: &str) -> Option<T> {
+    self.get(key).and_then(|value| value.parse().ok())
+  }
+
+  /// Reads a boolean the way the server does, where anything but `true` is
+  /// false.
+  fn boolean(&self, key: &str, default: bool) -> bool {
+    self
+      .get(key)
+      .map_or(default, |value| value.eq_ignore_ascii_case("true"))
+  }
+
+  /// Name of the directory holding the world, relative to the server
+  /// directory.
+  pub fn level_name(&self) -> &str {
+    self
+      .get("level-name")
+      .filter(|name| !name.is_empty())
+      .unwrap_or(DEFAULT_LEVEL_NAME)
+  }
+
+  pub fn level_seed(&self) -> Option<&str> {
+    self.get("level-seed").filter(|seed| !seed.is_empty())
+  }
+
+  pub fn set_level_seed(&mut self, seed: &str) {
+    self.set_unchecked("level-seed", seed.to_string());
+  }
+
+  /// Address the server listens on, if it is bound to a specific one.
+  pub fn server_ip(&self) -> Option<&str> {
+    self.get("server-ip").filter(|ip| !ip.is_empty())
+  }
+
+  pub fn server_port(&self) -> u16 {
+    self.parsed("server-port").unwrap_or(DEFAULT_SERVER_PORT)
+  }
+
+  pub fn set_server_port(&mut self, port: u16) {
+    self.set_unchecked("server-port", port.to_string());
+  }
+
+  pub fn motd(&self) -> &str {
+    self.get("motd").unwrap_or(DEFAULT_MOTD)
+  }
+
+  pub fn set_motd(&mut self, motd: &str) {
+    self.set_unchecked("motd", motd.to_string());
+  }
+
+  pub fn difficulty(&self) -> Difficulty {
+    self.parsed("difficulty").unwrap_or(Difficulty::Easy)
+  }
+
+  pub fn set_difficulty(&mut self, difficulty: Difficulty) {
+    self.set_unchecked("difficulty", difficulty.to_string());
+  }
+
+  pub fn gamemode(&self) -> GameMode {
+    self.parsed("gamemode").unwrap_or(GameMode::Survival)
+  }
+
+  pub fn set_gamemode(&mut self, gamemode: GameMode) {
+    self.set_unchecked("gamemode", gamemode.to_string());
+  }
+
+  pub fn max_players(&self) -> u32 {
+    self.parsed("max-players").unwrap_or(20)
+  }
+
+  pub fn set_max_players(&mut self, max_players: u32) {
+    self.set_unchecked("max-players", max_players.to_string());
+  }
+
+  pub fn online_mode(&self) -> bool {
+    self.boolean("online-mode", true)
+  }
+
+  pub fn set_online_mode(&mut self, online_mode: bool) {
+    self.set_unchecked("online-mode", online_mode.to_string());
+  }
+
+  pub fn view_distance(&self) -> u32 {
+    self.parsed("view-distance").unwrap_or(10)
+  }
+
+  pub fn set_view_distance(&mut self, view_distance: u32) {
+    self.set_unchecked("view-distance", view_distance.to_string());
+  }
+
+  pub fn enable_rcon(&self) -> bool {
+    self.boolean("enable-rcon", false)
+  }
+
+  pub fn set_enable_rcon(&mut self, enable_rcon: bool) {
+    self.set_unchecked("enable-rcon", enable_rcon.to_string());
+  }
+
+  pub fn rcon_port(&self) -> u16 {
+    self.parsed("rcon.port").unwrap_or(DEFAULT_RCON_PORT)
+  }
+
+  pub fn set_rcon_port(&mut self, port: u16) {
+    self.set_unchecked("rcon.port", port.to_string());
+  }
+
+  pub fn rcon_password(&self) -> Option<&str> {
+    self
+      .get("rcon.password")
+      .filter(|password| !password.is_empty())
+  }
+
+  pub fn set_rcon_password(&mut self, password: &str) {
+    self.set_unchecked("rcon.password", password.to_string());
+  }
+
+  pub fn enable_query(&self) -> bool {
+    self.boolean("enable-query", false)
+  }
+
+  pub fn set_enable_query(&mut self, enable_query: bool) {
+    self.set_unchecked("enable-query", enable_query.to_string());
+  }
+
+  /// Port for the query protocol, which defaults to the game port.
+  pub fn query_port(&self) -> u16 {
+    self
+      .parsed("query.port")
+      .unwrap_or_else(|| self.server_port())
+  }
+
+  pub fn set_query_port(&mut self, port: u16) {
+    self.set_unchecked("query.port", port.to_string());
+  }
+}
+
+impl Display for ServerProperties {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    let line_ending = self.line_ending.as_str();
+    // Only the last line read from the file can be missing its terminator,
+    // which it needs before any setting added after it.
+    let mut terminated = true;
+    for line in &self.lines {
+      match line {
+        PropertyLine::Other(text)
+        | PropertyLine::Entry {
+          raw: Some(text), ..
+        } => {
+          f.write_str(text)?;
+          terminated = text.ends_with('\n');
+        }
+        PropertyLine::Entry {
+          key,
+          value,
+          raw: None,
+        } => {
+          if !terminated {
+            f.write_str(line_ending)?;
+          }
+          write!(
+            f,
+            "{}={}{line_ending}",
+            escape(key, true),
+            escape(value, false)
+          )?;
+          terminated = true;
+        }
+      }
+    }
+    Ok(())
+  }
+}
+
+fn is_whitespace(c: char) -> bool {
+  matches!(c, ' ' | '\t' | '\x0c')
+}
+
+fn trim_start(line: &str) -> &str {
+  line.trim_start_matches(is_whitespace)
+}
+
+/// Removes the `\n` or `\r\n` that ends a physical line, if any.
+fn strip_line_ending(line: &str) -> &str {
+  let line = line.strip_suffix('\n').unwrap_or(line);
+  line.strip_suffix('\r').unwrap_or(line)
+}
+
+/// Whether `line` ends in an unescaped backslash, joining it to the next.
+fn ends_with_continuation(line: &str) -> bool {
+  line.chars().rev().take_while(|c| *c == '\\').count() % 2 == 1
+}
+
+/// Splits a logical line into its key and value, which end at the first
+/// unescaped `=`, `:` or whitespace.
+fn split_entry(line: &str) -> (String, String) {
+  let mut escaped = false;
+  let mut separator = None;
+  for (index, c) in line.char_indices() {
+    if escaped {
+      escaped = false;
+    } else if c == '\\' {
+      escaped = true;
+    } else if c == '=' || c == ':' || is_whitespace(c) {
+      separator = Some((index, c));
+      break;
+    }
+  }
+  let Some((index, c)) = separator else {
+    return (unescape(line), String::new());
+  };
+
+  let key = unescape(&line[..index]);
+  let mut rest = trim_start(&line[index + c.len_utf8()..]);
+  if is_whitespace(c) {
+    if let Some(after) = rest.strip_prefix(['=', ':']) {
+      rest = trim_start(after);
+    }
+  }
+  (key, unescape(rest))
+}
+
+/// Resolves the backslash escapes in a key or value.
+fn unescape(text: &str) -> String {
+  let mut units = Vec::with_capacity(text.len());
+  let mut chars = text.chars();
+  while let Some(c) = chars.next() {
+    if c != '\\' {
+      units.extend(c.encode_utf16(&mut [0; 2]).iter());
+      continue;
+    }
+    let unit = match chars.next() {
+      Some('t') => '\t' as u16,
+      Some('n') => '\n' as u16,
+      Some('r') => '\r' as u16,
+      Some('f') => '\x0c' as u16,
+      Some('u') => {
+        let digits: String = chars.by_ref().take(4).collect();
+        u16::from_str_radix(&digits, 16).unwrap_or(char::REPLACEMENT_CHARACTER as u16)
+      }
+      Some(c) => {
+        units.extend(c.encode_utf16(&mut [0; 2]).iter());
+        continue;
+      }
+      None => continue,
+    };
+    units.push(unit);
+  }
+  // `\u` escapes are UTF-16 code units, so characters outside the BMP are
+  // written as surrogate pairs.
+  String::from_utf16_lossy(&units)
+}
+
+/// Escapes `text` the way Java's `Properties.store` does, so that it reads
+/// back unchanged whichever encoding the server loads the file with.
+fn escape(text: &str, is_key: bool) -> String {
+  let mut escaped = String::with_capacity(text.len());
+  for (index, c) in text.chars().enumerate() {
+    match c {
+      '\\' => escaped.push_str("\\\\"),
+      '\n' => escaped.push_str("\\n"),
+      '\r' => escaped.push_str("\\r"),
+      '\t' => escaped.push_str("\\t"),
+      ' ' if is_key || index == 0 => escaped.push_str("\\ "),
+      '=' | ':' | '#' | '!' => {
+        escaped.push('\\');
+        escaped.push(c);
+      }
+      c if c.is_ascii() && !c.is_ascii_control() => escaped.push(c),
+      c => {
+        let mut units = [0; 2];
+        for unit in c.encode_utf16(&mut units) {
+          let _ = write!(escaped, "\\u{unit:04X}");
+        }
+      }
+    }
+  }
+  escaped
+}
+
+/// Settings for the `server.properties` of a new server. Anything left unset
+/// is filled in with its default by the server on first start.
+#[derive(Debug, Clone, Default)]
+pub struct ServerSettings {
+  pub seed: Option<String>,
+  pub gamemode: Option<GameMode>,
+  pub difficulty: Option<Difficulty>,
+  pub port: Option<u16>,
+  pub motd: Option<String>,
+}
+
+impl ServerSettings {
+  pub fn apply(&self, properties: &mut ServerProperties) {
+    if let Some(seed) = &self.seed {
+      properties.set_level_seed(seed);
+    }
+    if let Some(gamemode) = self.gamemode {
+      properties.set_gamemode(gamemode);
+    }
+    if let Some(difficulty) = self.difficulty {
+      properties.set_difficulty(difficulty);
+    }
+    if let Some(port) = self.port {
+      properties.set_server_port(port);
+    }
+    if let Some(motd) = &self.motd {
+      properties.set_motd(motd);
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::path::PathBuf;
+
+  const VANILLA: &str = "\
+#Minecraft server properties
+#Fri Oct 16 12:00:00 UTC 2026
+enable-jmx-monitoring=false
+rcon.port=25575
+level-seed=
+gamemode=survival
+motd=A Minecraft Server
+server-port=25565
+";
+
+  /// A fresh directory for a test to write files in.
+  fn test_directory(name: &str) -> PathBuf {
+    let directory =
+      std::env::temp_dir().join(format!("run-properties-{}-{name}", std::process::id()));
+    let _ = fs::remove_dir_all(&directory);
+    fs::create_dir_all(&directory).unwrap();
+    directory
+  }
+
+  #[test]
+  fn round_trips_unchanged() {
+    let contents = "\
+# comment
+! also a comment
+
+key = value with spaces  
+  indented:colon
+spaced value
+escaped\\ key=line one\\
+    line two
+motd=\\u00A7aGreen \\u00e9
+";
+    assert_eq!(ServerProperties::parse(contents).to_string(), contents);
+    assert_eq!(ServerProperties::parse(VANILLA).to_string(), VANILLA);
+  }
+
+  #[test]
+  fn keeps_crlf_line_endings() {
+    let contents = VANILLA.replace('\n', "\r\n");
+    let mut properties = ServerProperties::parse(&contents);
+    assert_eq!(properties.to_string(), contents);
+
+    properties.set("gamemode", "creative").unwrap();
+    properties.set("pvp", "false").unwrap();
+    let expected = contents.replace("gamemode=survival", "gamemode=creative") + "pvp=false\r\n";
+    assert_eq!(properties.to_string(), expected);
+
+    let properties = ServerProperties::parse("motd=Hello \\\r\n  world\r\n");
+    assert_eq!(properties.get("motd"), Some("Hello world"));
+  }
+
+  #[test]
+  fn keeps_missing_trailing_newline() {
+    let mut properties = ServerProperties::parse("# comment\nmotd=hi");
+    assert_eq!(properties.to_string(), "# comment\nmotd=hi");
+
+    properties.set("pvp", "false").unwrap();
+    assert_eq!(properties.to_string(), "# comment\nmotd=hi\npvp=false\n");
+  }
+
+  #[test]
+  fn splits_keys_and_values() {
+    let properties = ServerProperties::parse("a=1\nb:2\nc 3\nd = 4\ne   :   5\nf\ng==7\n  h=8\n");
+    let entries: Vec<_> = properties.iter().collect();
+    assert_eq!(
+      entries,
+      [
+        ("a", "1"),
+        ("b", "2"),
+        ("c", "3"),
+        ("d", "4"),
+        ("e", "5"),
+        ("f", ""),
+        ("g", "=7"),
+        ("h", "8"),
+      ]
+    );
+  }
+
+  #[test]
+  fn resolves_escapes() {
+    let properties = ServerProperties::parse(
+      "key\\=with\\:separators=value\ntabs=a\\tb\\nc\\\\d\nunknown=\\q\nspace\\ key=x\n",
+    );
+    assert_eq!(properties.get("key=with:separators"), Some("value"));
+    assert_eq!(properties.get("tabs"), Some("a\tb\nc\\d"));
+    assert_eq!(properties.get("unknown"), Some("q"));
+    assert_eq!(properties.get("space key"), Some("x"));
+  }
+
+  #[test]
+  fn decodes_surrogate_pairs() {
+    let properties = ServerProperties::parse("motd=\\u00A7l\\uD83D\\uDE00 \\u00e9\n");
+    assert_eq!(properties.get("motd"), Some("§l😀 é"));
+  }
+
+  #[test]
+  fn joins_continuation_lines() {
+    let properties = ServerProperties::parse("motd=Hello \\\n    world\nodd=a\\\\\nnext=b\n");
+    assert_eq!(properties.get("motd"), Some("Hello world"));
+    // An escaped backslash does not continue the line.
+    assert_eq!(properties.get("odd"), Some("a\\"));
+    assert_eq!(properties.get("next"), Some("b"));
+  }
+
+  #[test]
+  fn later_entries_win() {
+    let properties = ServerProperties::parse("motd=first\nmotd=second\n");
+    assert_eq!(properties.get("motd"), Some("second"));
+    assert_eq!(properties.iter().collect::<Vec<_>>(), [("motd", "second")]);
+  }
+
+  #[test]
+  fn changes_only_edited_lines() {
+    let mut properties = ServerProperties::parse(VANILLA);
+    properties.set("gamemode", "creative").unwrap();
+    properties.set("motd", "Ünïcode: 😀").unwrap();
+    properties.set("white-list", "true").unwrap();
+    assert_eq!(
+      properties.to_string(),
+      VANILLA
+        .replace("gamemode=survival", "gamemode=creative")
+        .replace(
+          "motd=A Minecraft Server",
+          "motd=\\u00DCn\\u00EFcode\\: \\uD83D\\uDE00"
+        )
+        + "white-list=true\n"
+    );
+    let reparsed = ServerProperties::parse(&properties.to_string());
+    assert_eq!(reparsed.motd(), "Ünïcode: 😀");
+  }
+
+  #[test]
+  fn rejects_invalid_values() {
+    let mut properties = ServerProperties::parse(VANILLA);
+    let error = properties.set("server-port", "70000").unwrap_err();
+    assert_eq!(error.key, "server-port");
+    assert!(properties.set("difficulty", "impossible").is_err());
+    assert!(properties.set("pvp", "yes").is_err());
+    assert!(properties.set("difficulty", "3").is_ok());
+    assert_eq!(properties.difficulty(), Difficulty::Hard);
+    assert_eq!(
+      properties.to_string(),
+      VANILLA.to_string() + "difficulty=3\n"
+    );
+  }
+
+  #[test]
+  fn keeps_latin1_files_in_latin1() {
+    let directory = test_directory("latin1");
+    let mut contents =
+      b"#Minecraft server properties\nmotd=Caf\xe9 \xa7aVert\ngamemode=survival\n".to_vec();
+    fs::write(directory.join(PROPERTIES_FILENAME), &contents).unwrap();
+
+    let mut properties = ServerProperties::load(&directory).unwrap();
+    assert_eq!(properties.motd(), "Café §aVert");
+    properties.set_gamemode(GameMode::Creative);
+    properties.save(&directory).unwrap();
+
+    contents.truncate(contents.len() - "survival\n".len());
+    contents.extend(b"creative\n");
+    assert_eq!(
+      fs::read(directory.join(PROPERTIES_FILENAME)).unwrap(),
+      contents
+    );
+    fs::remove_dir_all(directory).unwrap();
+  }
+
+  #[test]
+  fn keeps_utf8_files_in_utf8() {
+    let directory = test_directory("utf8");
+    let contents = "motd=Café\n";
+    fs::write(directory.join(PROPERTIES_FILENAME), contents).unwrap();
+    let properties = ServerProperties::load(&directory).unwrap();
+    assert_eq!(properties.motd(), "Café");
+    properties.save(&directory).unwrap();
+    assert_eq!(
+      fs::read_to_string(directory.join(PROPERTIES_FILENAME)).unwrap(),
+      contents
+    );
+    fs::remove_dir_all(directory).unwrap();
+  }
+}
@@ -0,0 +1,311 @@
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::time::Duration;
+use thiserror::Error;
+use tokio::net::{lookup_host, UdpSocket};
+use tokio::time;
+
+const MAGIC: [u8; 2] = [0xFE, 0xFD];
+const HANDSHAKE: u8 = 0x09;
+const STAT: u8 = 0x00;
+/// Only the low four bits of each byte of the session ID are used.
+const SESSION_ID_MASK: i32 = 0x0F0F_0F0F;
+/// Padding that makes a stat request ask for the full rather than basic stat.
+const FULL_STAT_PADDING: [u8; 4] = [0; 4];
+/// Fixed text before the key-value section of a full stat response.
+const STAT_HEADER: &[u8] = b"splitnum\0\x80\0";
+/// Fixed text between the key-value and player sections.
+const PLAYERS_HEADER: &[u8] = b"\x01player_\0\0";
+const MAX_RESPONSE_LENGTH: usize = 64 * 1024;
+/// How many times each request is sent within the timeout, as datagrams can
+/// be lost on the way.
+const SEND_ATTEMPTS: u32 = 3;
+
+#[derive(Debug, Error)]
+pub enum QueryError {
+  #[error("error in communicating with the server")]
+  IoError(#[from] std::io::Error),
+  #[error("could not resolve the server address")]
+  UnknownHost,
+  #[error("server did not answer in time, is enable-query set?")]
+  Timeout,
+  #[error("server sent a malformed query response")]
+  MalformedResponse,
+}
+
+/// Everything a server reports over the query protocol.
+#[derive(Debug, Clone, Default)]
+pub struct QueryStats {
+  /// Message of the day, which may contain `§` formatting codes
+  pub motd: String,
+  pub game_type: String,
+  pub version: String,
+  /// Server software, such as `Paper on 1.20.1`, if it reports any
+  pub server_mod: Option<String>,
+  pub plugins: Vec<String>,
+  /// Name of the world
+  pub map: String,
+  pub online_players: u32,
+  pub max_players: u32,
+  pub host_ip: String,
+  pub host_port: u16,
+  /// Names of all players online
+  pub players: Vec<String>,
+}
+
+/// Asks the server at `host` and `port` for its full stats over the query
+/// protocol, which it only answers with `enable-query=true`.
+pub async fn query(host: &str, port: u16, timeout: Duration) -> Result<QueryStats, QueryError> {
+  let address = lookup_host((host, port))
+    .await?
+    .next()
+    .ok_or(QueryError::UnknownHost)?;
+  let local: SocketAddr = match address {
+    SocketAddr::V4(_) => (Ipv4Addr::UNSPECIFIED, 0).into(),
+    SocketAddr::V6(_) => (Ipv6Addr::UNSPECIFIED, 0).into(),
+  };
+  let socket = UdpSocket::bind(local).await?;
+  socket.connect(address).await?;
+
+  time::timeout(timeout, full_stat(&socket, timeout / SEND_ATTEMPTS))
+    .await
+    .map_err(|_| QueryError::Timeout)?
+}
+
+async fn full_stat(socket: &UdpSocket, resend_after: Duration) -> Result<QueryStats, QueryError> {
+  let session_id = std::process::id() as i32 & SESSION_ID_MASK;
+
+  let response = request(socket, HANDSHAKE, session_id, &[], resend_after).await?;
+  let token = split_string(&mut response.as_slice())
+    .and_then(|token| token.parse::<i32>().ok())
+    .ok_or(QueryError::MalformedResponse)?;
+
+  let mut payload = token.to_be_bytes().to_vec();
+  payload.extend(FULL_STAT_PADDING);
+  let response = request(socket, STAT, session_id, &payload, resend_after).await?;
+  parse_full_stat(&response).ok_or(QueryError::MalformedResponse)
+}
+
+/// Sends a request and returns the body of the matching response, sending it
+/// again whenever `resend_after` passes without one.
+async fn request(
+  socket: &UdpSocket,
+  kind: u8,
+  session_id: i32,
+  payload: &[u8],
+  resend_after: Duration,
+) -> Result<Vec<u8>, QueryError> {
+  let mut packet = MAGIC.to_vec();
+  packet.push(kind);
+  packet.extend(session_id.to_be_bytes());
+  packet.extend(payload);
+  socket.send(&packet).await?;
+
+  let mut buffer = vec![0; MAX_RESPONSE_LENGTH];
+  loop {
+    let Ok(length) = time::timeout(resend_after, socket.recv(&mut buffer)).await else {
+      socket.send(&packet).await?;
+      continue;
+    };
+    let response = &buffer[..length?];
+    // Skip stray answers to earlier requests.
+    if response.len() >= 5 && response[0] == kind && response[1..5] == session_id.to_be_bytes() {
+      return Ok(response[5..].to_vec());
+    }
+  }
+}
+
+/// Takes a null-terminated string off the front of `bytes`.
+fn split_string(bytes: &mut &[u8]) -> Option<String> {
+  let end = bytes.iter().position(|byte| *byte == 0)?;
+  let text = String::from_utf8_lossy(&bytes[..end]).into_owned();
+  *bytes = &bytes[end + 1..];
+  Some(text)
+}
+
+fn parse_full_stat(response: &[u8]) -> Option<QueryStats> {
+  let mut bytes = response.strip_prefix(STAT_HEADER)?;
+  let mut stats = QueryStats::default();
+  loop {
+    let key = split_string(&mut bytes)?;
+    if key.is_empty() {
+      break;
+    }
+    let value = split_string(&mut bytes)?;
+    match key.as_str() {
+      "hostname" => stats.motd = value,
+      "gametype" => stats.game_type = value,
+      "version" => stats.version = value,
+      "plugins" => (stats.server_mod, stats.plugins) = parse_plugins(&value),
+      "map" => stats.map = value,
+      "numplayers" => stats.online_players = value.parse().ok()?,
+      "maxplayers" => stats.max_players = value.parse().ok()?,
+      "hostport" => stats.host_port = value.parse().ok()?,
+      "hostip" => stats.host_ip = value,
+      _ => {}
+    }
+  }
+
+  let mut bytes = bytes.strip_prefix(PLAYERS_HEADER)?;
+  loop {
+    let player = split_string(&mut bytes)?;
+    if player.is_empty() {
+      break;
+    }
+    stats.players.push(player);
+  }
+  Some(stats)
+}
+
+/// Splits the `plugins` value, which looks like
+/// `Paper on 1.20.1: WorldEdit 7.2.15; LuckPerms 5.4` when the server reports
+/// its software and plugins.
+fn parse_plugins(value: &str) -> (Option<String>, Vec<String>) {
+  let Some((server_mod, plugins)) = value.split_once(": ") else {
+    let server_mod = Some(value.to_string()).filter(|value| !value.is_empty());
+    return (server_mod, Vec::new());
+  };
+  let plugins = plugins
+    .split("; ")
+    .filter(|plugin| !plugin.is_empty())
+    .map(str::to_string)
+    .collect();
+  (Some(server_mod.to_string()), plugins)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::collections::HashSet;
+
+  const TOKEN: i32 = 9513307;
+  const FULL_STAT: &[u8] = b"splitnum\x00\x80\x00hostname\x00A Paper server\x00gametype\x00SMP\x00\
+    game_id\x00MINECRAFT\x00version\x001.20.1\x00plugins\x00Paper on 1.20.1: WorldEdit 7.2.15; LuckPerms 5.4\x00\
+    map\x00world\x00numplayers\x002\x00maxplayers\x0020\x00hostport\x0025565\x00hostip\x00127.0.0.1\x00\x00\
+    \x01player_\x00\x00Steve\x00Alex\x00\x00";
+
+  #[derive(Clone, Copy, PartialEq, Eq)]
+  enum FakeServer {
+    Answering,
+    /// Ignores the first request of each kind, as if it had been lost
+    Lossy,
+    Silent,
+  }
+
+  /// Starts a fake server that answers query requests as `server`.
+  async fn serve(server: FakeServer) -> u16 {
+    let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let port = socket.local_addr().unwrap().port();
+    tokio::spawn(async move {
+      let mut buffer = [0; 1024];
+      let mut seen = HashSet::new();
+      loop {
+        let (length, peer) = socket.recv_from(&mut buffer).await.unwrap();
+        let request = &buffer[..length];
+        assert_eq!(request[..2], MAGIC);
+        let (kind, session_id, payload) = (request[2], &request[3..7], &request[7..]);
+        let first = seen.insert(kind);
+        if server == FakeServer::Silent || (server == FakeServer::Lossy && first) {
+          continue;
+        }
+
+        let mut response = vec![kind];
+        response.extend(session_id);
+        match kind {
+          HANDSHAKE => {
+            assert!(payload.is_empty());
+            response.extend(format!("{TOKEN}\0").as_bytes());
+          }
+          STAT => {
+            assert_eq!(
+              payload,
+              [&TOKEN.to_be_bytes()[..], &FULL_STAT_PADDING].concat()
+            );
+            response.extend(FULL_STAT);
+          }
+          _ => panic!("unexpected request type {kind}"),
+        }
+        socket.send_to(&response, peer).await.unwrap();
+      }
+    });
+    port
+  }
+
+  fn assert_full_stat(stats: &QueryStats) {
+    assert_eq!(stats.motd, "A Paper server");
+    assert_eq!(stats.game_type, "SMP");
+    assert_eq!(stats.version, "1.20.1");
+    assert_eq!(stats.server_mod.as_deref(), Some("Paper on 1.20.1"));
+    assert_eq!(stats.plugins, ["WorldEdit 7.2.15", "LuckPerms 5.4"]);
+    assert_eq!(stats.map, "world");
+    assert_eq!(stats.online_players, 2);
+    assert_eq!(stats.max_players, 20);
+    assert_eq!(stats.host_ip, "127.0.0.1");
+    assert_eq!(stats.host_port, 25565);
+    assert_eq!(stats.players, ["Steve", "Alex"]);
+  }
+
+  #[tokio::test]
+  async fn queries_server() {
+    let port = serve(FakeServer::Answering).await;
+    let stats = query("127.0.0.1", port, Duration::from_secs(5))
+      .await
+      .unwrap();
+    assert_full_stat(&stats);
+  }
+
+  #[tokio::test]
+  async fn resends_lost_requests() {
+    let port = serve(FakeServer::Lossy).await;
+    let stats = query("127.0.0.1", port, Duration::from_secs(1))
+      .await
+      .unwrap();
+    assert_full_stat(&stats);
+  }
+
+  #[tokio::test]
+  async fn times_out_without_answer() {
+    let port = serve(FakeServer::Silent).await;
+    let result = query("127.0.0.1", port, Duration::from_millis(300)).await;
+    assert!(matches!(result, Err(QueryError::Timeout)));
+  }
+
+  #[test]
+  fn parses_full_stat() {
+    assert_full_stat(&parse_full_stat(FULL_STAT).unwrap());
+  }
+
+  #[test]
+  fn rejects_truncated_full_stat() {
+    for length in 0..FULL_STAT.len() {
+      assert!(
+        parse_full_stat(&FULL_STAT[..length]).is_none(),
+        "accepted the first {length} bytes"
+      );
+    }
+  }
+
+  #[test]
+  fn rejects_malformed_full_stat() {
+    let response = [STAT_HEADER, b"numplayers\0two\0\0", PLAYERS_HEADER, b"\0"].concat();
+    assert!(parse_full_stat(&response).is_none());
+    let response = [STAT_HEADER, b"map\0world\0\0", b"player_\0\0\0"].concat();
+    assert!(parse_full_stat(&response).is_none());
+    assert!(parse_full_stat(&FULL_STAT[STAT_HEADER.len()..]).is_none());
+  }
+
+  #[test]
+  fn splits_plugins() {
+    assert_eq!(parse_plugins(""), (None, Vec::new()));
+    assert_eq!(
+      parse_plugins("Paper on 1.20.1"),
+      (Some("Paper on 1.20.1".to_string()), Vec::new())
+    );
+    assert_eq!(
+      parse_plugins("Paper on 1.20.1: A; B"),
+      (
+        Some("Paper on 1.20.1".to_string()),
+        vec!["A".to_string(), "B".to_string()]
+      )
+    );
+  }
+}
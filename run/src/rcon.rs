@@ -0,0 +1,277 @@
+use thiserror::Error;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpStream, ToSocketAddrs};
+
+const AUTH: i32 = 3;
+const AUTH_RESPONSE: i32 = 2;
+const EXEC_COMMAND: i32 = 2;
+const RESPONSE_VALUE: i32 = 0;
+
+/// Longest command body the Minecraft server accepts.
+pub const MAX_COMMAND_LENGTH: usize = 1446;
+/// Largest packet accepted from the server, well above the 4096 byte bodies
+/// Minecraft sends, to guard against garbage lengths.
+const MAX_PACKET_LENGTH: i32 = 64 * 1024;
+/// Size of the request ID, type and two terminating nulls around a body.
+const PACKET_OVERHEAD: i32 = 10;
+
+#[derive(Debug, Error)]
+pub enum RconError {
+  #[error("error in communicating with the RCON server")]
+  IoError(#[from] std::io::Error),
+  #[error("RCON password was rejected")]
+  AuthenticationFailed,
+  #[error("command is {0} bytes long, but RCON allows at most {MAX_COMMAND_LENGTH}")]
+  CommandTooLong(usize),
+  #[error("RCON server sent a malformed packet")]
+  MalformedPacket,
+}
+
+struct Packet {
+  id: i32,
+  kind: i32,
+  body: String,
+}
+
+/// A connection to a server's remote console, speaking the Source RCON
+/// protocol.
+pub struct RconClient {
+  stream: TcpStream,
+  next_id: i32,
+}
+
+impl RconClient {
+  /// Connects to the server at `address` and logs in with `password`.
+  pub async fn connect(address: impl ToSocketAddrs, password: &str) -> Result<Self, RconError> {
+    let mut client = Self {
+      stream: TcpStream::connect(address).await?,
+      next_id: 1,
+    };
+    let id = client.send(AUTH, password).await?;
+    loop {
+      let packet = client.receive().await?;
+      // Source servers send an empty response ahead of the result.
+      if packet.kind != AUTH_RESPONSE {
+        continue;
+      }
+      if packet.id == id {
+        return Ok(client);
+      }
+      return Err(RconError::AuthenticationFailed);
+    }
+  }
+
+  /// Runs `command` on the server and returns its output.
+  pub async fn command(&mut self, command: &str) -> Result<String, RconError> {
+    if command.len() > MAX_COMMAND_LENGTH {
+      return Err(RconError::CommandTooLong(command.len()));
+    }
+    let id = self.send(EXEC_COMMAND, command).await?;
+    // Long output is split across several packets with no marker for the last
+    // one, so follow the command with a request the server answers only after
+    // it, and collect everything up to that answer.
+    let end_id = self.send(RESPONSE_VALUE, "").await?;
+
+    let mut output = String::new();
+    loop {
+      let packet = self.receive().await?;
+      if packet.id == end_id {
+        break;
+      }
+      // Anything else is left over from earlier commands, such as the second
+      // answer Source servers give to the trailing request.
+      if packet.id == id && packet.kind == RESPONSE_VALUE {
+        output.push_str(&packet.body);
+      }
+    }
+    Ok(output)
+  }
+
+  async fn send(&mut self, kind: i32, body: &str) -> Result<i32, RconError> {
+    let id = self.next_id;
+    self.next_id = self.next_id.wrapping_add(1).max(1);
+
+    let mut packet = Vec::with_capacity(body.len() + 14);
+    packet.extend((body.len() as i32 + PACKET_OVERHEAD).to_le_bytes());
+    packet.extend(id.to_le_bytes());
+    packet.extend(kind.to_le_bytes());
+    packet.extend(body.as_bytes());
+    packet.extend([0, 0]);
+    self.stream.write_all(&packet).await?;
+    Ok(id)
+  }
+
+  async fn receive(&mut self) -> Result<Packet, RconError> {
+    let length = self.stream.read_i32_le().await?;
+    if !(PACKET_OVERHEAD..=MAX_PACKET_LENGTH).contains(&length) {
+      return Err(RconError::MalformedPacket);
+    }
+    let mut packet = vec![0; length as usize];
+    self.stream.read_exact(&mut packet).await?;
+
+    let id = i32::from_le_bytes(packet[0..4].try_into().expect("Slice is 4 bytes long"));
+    let kind = i32::from_le_bytes(packet[4..8].try_into().expect("Slice is 4 bytes long"));
+    let body = packet[8..]
+      .strip_suffix(&[0, 0])
+      .ok_or(RconError::MalformedPacket)?;
+    Ok(Packet {
+      id,
+      kind,
+      body: String::from_utf8_lossy(body).into_owned(),
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::future::Future;
+  use std::net::SocketAddr;
+  use tokio::net::TcpListener;
+
+  const PASSWORD: &str = "hunter2";
+
+  /// Starts a fake RCON server that handles a single connection.
+  async fn serve<F, Fut>(handle: F) -> SocketAddr
+  where
+    F: FnOnce(TcpStream) -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + Send,
+  {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+      let (stream, _) = listener.accept().await.unwrap();
+      handle(stream).await;
+    });
+    address
+  }
+
+  async fn read_packet(stream: &mut TcpStream) -> (i32, i32, String) {
+    let length = stream.read_i32_le().await.unwrap();
+    let mut packet = vec![0; length as usize];
+    stream.read_exact(&mut packet).await.unwrap();
+    let id = i32::from_le_bytes(packet[0..4].try_into().unwrap());
+    let kind = i32::from_le_bytes(packet[4..8].try_into().unwrap());
+    let body = String::from_utf8(packet[8..packet.len() - 2].to_vec()).unwrap();
+    (id, kind, body)
+  }
+
+  async fn write_packet(stream: &mut TcpStream, id: i32, kind: i32, body: &str) {
+    let mut packet = (body.len() as i32 + PACKET_OVERHEAD).to_le_bytes().to_vec();
+    packet.extend(id.to_le_bytes());
+    packet.extend(kind.to_le_bytes());
+    packet.extend(body.as_bytes());
+    packet.extend([0, 0]);
+    stream.write_all(&packet).await.unwrap();
+  }
+
+  /// Answers the login the way Minecraft does, accepting only [`PASSWORD`].
+  async fn log_in(stream: &mut TcpStream) {
+    let (id, kind, body) = read_packet(stream).await;
+    assert_eq!(kind, AUTH);
+    let id = if body == PASSWORD { id } else { -1 };
+    write_packet(stream, id, AUTH_RESPONSE, "").await;
+  }
+
+  #[tokio::test]
+  async fn logs_in() {
+    let address = serve(|mut stream| async move { log_in(&mut stream).await }).await;
+    assert!(RconClient::connect(address, PASSWORD).await.is_ok());
+  }
+
+  #[tokio::test]
+  async fn rejects_wrong_password() {
+    let address = serve(|mut stream| async move {
+      // Source servers send an empty response ahead of the result.
+      let (id, _, _) = read_packet(&mut stream).await;
+      write_packet(&mut stream, id, RESPONSE_VALUE, "").await;
+      write_packet(&mut stream, -1, AUTH_RESPONSE, "").await;
+    })
+    .await;
+    let result = RconClient::connect(address, "wrong").await;
+    assert!(matches!(result, Err(RconError::AuthenticationFailed)));
+  }
+
+  #[tokio::test]
+  async fn joins_split_response() {
+    let address = serve(|mut stream| async move {
+      log_in(&mut stream).await;
+      let (id, kind, body) = read_packet(&mut stream).await;
+      assert_eq!((kind, body.as_str()), (EXEC_COMMAND, "list"));
+      let (end_id, kind, body) = read_packet(&mut stream).await;
+      assert_eq!((kind, body.as_str()), (RESPONSE_VALUE, ""));
+      write_packet(&mut stream, id, RESPONSE_VALUE, "There are 2 of a max ").await;
+      write_packet(&mut stream, id, RESPONSE_VALUE, "of 20 players online: ").await;
+      write_packet(&mut stream, id, RESPONSE_VALUE, "Steve, Alex").await;
+      write_packet(&mut stream, end_id, RESPONSE_VALUE, "").await;
+      // Source servers answer the trailing request twice.
+      write_packet(&mut stream, end_id, RESPONSE_VALUE, "\u{0}\u{1}").await;
+
+      let (id, _, _) = read_packet(&mut stream).await;
+      let (end_id, _, _) = read_packet(&mut stream).await;
+      write_packet(&mut stream, id, RESPONSE_VALUE, "Set the time to 1000").await;
+      write_packet(&mut stream, end_id, RESPONSE_VALUE, "").await;
+    })
+    .await;
+    let mut client = RconClient::connect(address, PASSWORD).await.unwrap();
+    assert_eq!(
+      client.command("list").await.unwrap(),
+      "There are 2 of a max of 20 players online: Steve, Alex"
+    );
+    assert_eq!(
+      client.command("time set 1000").await.unwrap(),
+      "Set the time to 1000"
+    );
+  }
+
+  #[tokio::test]
+  async fn refuses_long_commands() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    let server = tokio::spawn(async move {
+      let (mut stream, _) = listener.accept().await.unwrap();
+      log_in(&mut stream).await;
+      let mut rest = Vec::new();
+      stream.read_to_end(&mut rest).await.unwrap();
+      rest
+    });
+    let mut client = RconClient::connect(address, PASSWORD).await.unwrap();
+    let command = "say ".to_string() + &"a".repeat(MAX_COMMAND_LENGTH);
+    let result = client.command(&command).await;
+    assert!(
+      matches!(result, Err(RconError::CommandTooLong(length)) if length == MAX_COMMAND_LENGTH + 4)
+    );
+    drop(client);
+    assert!(server.await.unwrap().is_empty(), "Nothing should be sent");
+  }
+
+  async fn connect_with_reply(reply: Vec<u8>) -> Result<RconClient, RconError> {
+    let address = serve(|mut stream| async move {
+      read_packet(&mut stream).await;
+      stream.write_all(&reply).await.unwrap();
+    })
+    .await;
+    RconClient::connect(address, PASSWORD).await
+  }
+
+  #[tokio::test]
+  async fn rejects_bad_lengths() {
+    for length in [0, PACKET_OVERHEAD - 1, MAX_PACKET_LENGTH + 1, -1] {
+      let result = connect_with_reply(length.to_le_bytes().to_vec()).await;
+      assert!(
+        matches!(result, Err(RconError::MalformedPacket)),
+        "Length {length} should be rejected"
+      );
+    }
+  }
+
+  #[tokio::test]
+  async fn rejects_unterminated_body() {
+    let mut reply = PACKET_OVERHEAD.to_le_bytes().to_vec();
+    reply.extend(1i32.to_le_bytes());
+    reply.extend(AUTH_RESPONSE.to_le_bytes());
+    reply.extend(b"ab");
+    let result = connect_with_reply(reply).await;
+    assert!(matches!(result, Err(RconError::MalformedPacket)));
+  }
+}
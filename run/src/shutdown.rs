@@ -0,0 +1,218 @@
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::process::{Child, ChildStdin};
+use tokio::sync::watch;
+use tokio::time;
+
+/// How long to wait for the JVM to exit after `SIGTERM` before killing it.
+const TERMINATE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The path taken to bring the server down after a shutdown was requested.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShutdownMethod {
+  /// The server saved and exited after receiving `stop`
+  Stopped,
+  /// The server did not respond to `stop` in time and was sent `SIGTERM`
+  Terminated,
+  /// The server did not respond to either and was killed
+  Killed,
+  /// Shutdown was forced, such as by a second signal, before the server had
+  /// stopped, so it was sent `SIGTERM` straight away or killed
+  Forced,
+}
+
+/// How firmly the server has been asked to shut down, from least to most.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ShutdownRequest {
+  #[default]
+  None,
+  /// Stop the server through its console
+  Graceful,
+  /// Skip waiting for the server to stop by itself
+  Forced,
+}
+
+/// Raises the request in `sender` to `request`, leaving a firmer one alone.
+pub fn request(sender: &watch::Sender<ShutdownRequest>, request: ShutdownRequest) {
+  sender.send_if_modified(|current| {
+    let raised = request > *current;
+    if raised {
+      *current = request;
+    }
+    raised
+  });
+}
+
+/// Resolves once the process is asked to shut down by SIGINT or SIGTERM.
+pub async fn shutdown_signal() -> std::io::Result<()> {
+  #[cfg(unix)]
+  {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut terminate = signal(SignalKind::terminate())?;
+    tokio::select! {
+      result = tokio::signal::ctrl_c() => result,
+      _ = terminate.recv() => Ok(()),
+    }
+  }
+
+  #[cfg(not(unix))]
+  tokio::signal::ctrl_c().await
+}
+
+/// Resolves once shutdown has been requested at least as firmly as
+/// `request`, or never if the sender is gone.
+pub async fn requested(requested: &mut watch::Receiver<ShutdownRequest>, request: ShutdownRequest) {
+  if requested
+    .wait_for(|current| *current >= request)
+    .await
+    .is_err()
+  {
+    std::future::pending::<()>().await;
+  }
+}
+
+#[cfg(unix)]
+fn terminate(server: &Child) -> bool {
+  match server.id() {
+    // SAFETY: `kill` has no memory safety requirements, and the id belongs to
+    // a child we have not yet reaped so cannot have been reused.
+    Some(id) => unsafe { libc::kill(id as libc::pid_t, libc::SIGTERM) == 0 },
+    None => false,
+  }
+}
+
+#[cfg(not(unix))]
+fn terminate(_server: &Child) -> bool {
+  false
+}
+
+/// Waits up to `timeout` for the server to exit, returning whether it did, or
+/// `None` if shutdown is forced first.
+async fn wait_for_exit(
+  server: &mut Child,
+  timeout: Duration,
+  requested: &mut watch::Receiver<ShutdownRequest>,
+) -> Option<bool> {
+  tokio::select! {
+    result = time::timeout(timeout, server.wait()) => Some(result.is_ok()),
+    () = self::requested(requested, ShutdownRequest::Forced) => None,
+  }
+}
+
+/// Asks the server to stop through its console, escalating to `SIGTERM` and
+/// then `SIGKILL` if it does not exit within `timeout`. Once shutdown is
+/// forced through `requested`, the server gets no more time to stop by itself.
+pub async fn stop_server(
+  server: &mut Child,
+  console: &mut ChildStdin,
+  timeout: Duration,
+  requested: &mut watch::Receiver<ShutdownRequest>,
+) -> std::io::Result<ShutdownMethod> {
+  let mut forced = false;
+  if console.write_all(b"stop\n").await.is_ok() && console.flush().await.is_ok() {
+    match wait_for_exit(server, timeout, requested).await {
+      Some(true) => return Ok(ShutdownMethod::Stopped),
+      Some(false) => {}
+      None => forced = true,
+    }
+  }
+
+  if terminate(server) {
+    let exited = if forced {
+      Some(
+        time::timeout(TERMINATE_TIMEOUT, server.wait())
+          .await
+          .is_ok(),
+      )
+    } else {
+      wait_for_exit(server, TERMINATE_TIMEOUT, requested).await
+    };
+    match exited {
+      Some(true) if forced => return Ok(ShutdownMethod::Forced),
+      Some(true) => return Ok(ShutdownMethod::Terminated),
+      Some(false) => {}
+      None => forced = true,
+    }
+  }
+
+  server.kill().await?;
+  Ok(if forced {
+    ShutdownMethod::Forced
+  } else {
+    ShutdownMethod::Killed
+  })
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+  use super::*;
+  use std::process::Stdio;
+  use tokio::process::Command;
+
+  /// Starts `script` in a shell with its stdin piped, standing in for a server.
+  fn spawn(script: &str) -> (Child, ChildStdin) {
+    let mut child = Command::new("sh")
+      .args(["-c", script])
+      .stdin(Stdio::piped())
+      .kill_on_drop(true)
+      .spawn()
+      .unwrap();
+    let stdin = child.stdin.take().unwrap();
+    (child, stdin)
+  }
+
+  async fn stop(script: &str, timeout: Duration, request: ShutdownRequest) -> ShutdownMethod {
+    let (mut server, mut console) = spawn(script);
+    let (_sender, mut requested) = watch::channel(request);
+    time::timeout(
+      Duration::from_secs(5),
+      stop_server(&mut server, &mut console, timeout, &mut requested),
+    )
+    .await
+    .unwrap()
+    .unwrap()
+  }
+
+  #[tokio::test]
+  async fn stops_through_console() {
+    assert_eq!(
+      stop(
+        "read line; [ \"$line\" = stop ]",
+        Duration::from_secs(5),
+        ShutdownRequest::Graceful,
+      )
+      .await,
+      ShutdownMethod::Stopped
+    );
+  }
+
+  #[tokio::test]
+  async fn terminates_after_timeout() {
+    assert_eq!(
+      stop(
+        "sleep 30",
+        Duration::from_millis(100),
+        ShutdownRequest::Graceful,
+      )
+      .await,
+      ShutdownMethod::Terminated
+    );
+  }
+
+  #[tokio::test]
+  async fn skips_waiting_when_forced() {
+    assert_eq!(
+      stop("sleep 30", Duration::from_secs(30), ShutdownRequest::Forced).await,
+      ShutdownMethod::Forced
+    );
+  }
+
+  #[test]
+  fn never_lowers_request() {
+    let (sender, receiver) = watch::channel(ShutdownRequest::None);
+    request(&sender, ShutdownRequest::Forced);
+    request(&sender, ShutdownRequest::Graceful);
+    assert_eq!(*receiver.borrow(), ShutdownRequest::Forced);
+  }
+}
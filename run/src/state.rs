@@ -0,0 +1,45 @@
+use crate::flavor::Flavor;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+use thiserror::Error;
+
+const STATE_FILENAME: &str = ".minecraft_tools.json";
+
+#[derive(Debug, Error)]
+pub enum StateError {
+  #[error("error in accessing server state file")]
+  IoError(#[from] std::io::Error),
+  #[error("server state file is malformed")]
+  SerdeError(#[from] serde_json::Error),
+}
+
+/// Choices persisted in a server directory between runs.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ServerState {
+  pub flavor: Option<Flavor>,
+  pub version: Option<String>,
+  /// Set by `init` until the server has generated its world, which it does
+  /// on first start.
+  pub world_pending: bool,
+}
+
+impl ServerState {
+  pub fn load(directory: &Path) -> Result<Self, StateError> {
+    match fs::read_to_string(directory.join(STATE_FILENAME)) {
+      Ok(contents) => Ok(serde_json::from_str(&contents)?),
+      Err(error) if error.kind() == ErrorKind::NotFound => Ok(Self::default()),
+      Err(error) => Err(error.into()),
+    }
+  }
+
+  pub fn save(&self, directory: &Path) -> Result<(), StateError> {
+    fs::write(
+      directory.join(STATE_FILENAME),
+      serde_json::to_string_pretty(self)?,
+    )?;
+    Ok(())
+  }
+}
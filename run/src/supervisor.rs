@@ -0,0 +1,198 @@
+use clap::ValueEnum;
+use std::collections::{HashSet, VecDeque};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::process::ExitStatus;
+use std::time::{Duration, Instant};
+
+/// When to start the server again after it exits on its own.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum RestartMode {
+  #[default]
+  Never,
+  /// Restart after a non-zero exit or a new crash report
+  OnFailure,
+  Always,
+}
+
+#[derive(Debug, Clone)]
+pub struct RestartPolicy {
+  pub mode: RestartMode,
+  /// Most restarts allowed within `window` before giving up
+  pub max_restarts: u32,
+  pub window: Duration,
+  /// Delay before restarting, doubled for every restart already made within
+  /// `window`
+  pub backoff: Duration,
+  pub max_backoff: Duration,
+}
+
+impl Default for RestartPolicy {
+  fn default() -> Self {
+    Self {
+      mode: RestartMode::Never,
+      max_restarts: 5,
+      window: Duration::from_secs(10 * 60),
+      backoff: Duration::from_secs(5),
+      max_backoff: Duration::from_secs(5 * 60),
+    }
+  }
+}
+
+/// Tracks restarts made under a [`RestartPolicy`].
+pub struct Supervisor {
+  policy: RestartPolicy,
+  restarts: VecDeque<Instant>,
+}
+
+impl Supervisor {
+  pub fn new(policy: RestartPolicy) -> Self {
+    Self {
+      policy,
+      restarts: VecDeque::new(),
+    }
+  }
+
+  /// Decides whether to restart a server which exited with `status`, returning
+  /// how long to wait first.
+  pub fn next_restart(&mut self, status: ExitStatus, crashed: bool) -> Option<Duration> {
+    let failed = crashed || !status.success();
+    let wanted = match self.policy.mode {
+      RestartMode::Never => false,
+      RestartMode::OnFailure => failed,
+      RestartMode::Always => true,
+    };
+    if !wanted {
+      return None;
+    }
+
+    let now = Instant::now();
+    while let Some(&restart) = self.restarts.front() {
+      if now - restart < self.policy.window {
+        break;
+      }
+      self.restarts.pop_front();
+    }
+    let recent_restarts = self.restarts.len() as u32;
+    if recent_restarts >= self.policy.max_restarts {
+      return None;
+    }
+
+    self.restarts.push_back(now);
+    Some(
+      self
+        .policy
+        .backoff
+        .saturating_mul(2u32.saturating_pow(recent_restarts))
+        .min(self.policy.max_backoff),
+    )
+  }
+}
+
+/// Lists the files in the server's `crash-reports` directory.
+pub fn crash_reports(directory: &Path) -> std::io::Result<HashSet<PathBuf>> {
+  match fs::read_dir(directory.join("crash-reports")) {
+    Ok(entries) => entries
+      .map(|entry| entry.map(|entry| entry.path()))
+      .collect(),
+    Err(error) if error.kind() == ErrorKind::NotFound => Ok(HashSet::new()),
+    Err(error) => Err(error),
+  }
+}
+
+/// Finds a crash report written since `previous` was listed.
+pub fn new_crash_report(
+  directory: &Path,
+  previous: &HashSet<PathBuf>,
+) -> std::io::Result<Option<PathBuf>> {
+  Ok(
+    crash_reports(directory)?
+      .into_iter()
+      .filter(|report| !previous.contains(report))
+      .max(),
+  )
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[cfg(unix)]
+  fn exit_status(code: i32) -> ExitStatus {
+    std::os::unix::process::ExitStatusExt::from_raw(code << 8)
+  }
+
+  #[cfg(windows)]
+  fn exit_status(code: i32) -> ExitStatus {
+    std::os::windows::process::ExitStatusExt::from_raw(code as u32)
+  }
+
+  fn policy(mode: RestartMode) -> RestartPolicy {
+    RestartPolicy {
+      mode,
+      max_restarts: 3,
+      window: Duration::from_secs(60 * 60),
+      backoff: Duration::from_secs(5),
+      max_backoff: Duration::from_secs(60),
+    }
+  }
+
+  #[test]
+  fn never_restarts_by_default() {
+    let mut supervisor = Supervisor::new(RestartPolicy::default());
+    assert_eq!(supervisor.next_restart(exit_status(1), true), None);
+  }
+
+  #[test]
+  fn restarts_on_failure_only_after_failures() {
+    let mut supervisor = Supervisor::new(policy(RestartMode::OnFailure));
+    assert_eq!(supervisor.next_restart(exit_status(0), false), None);
+    assert!(supervisor.next_restart(exit_status(1), false).is_some());
+    // A crash report counts as a failure even if the server exits cleanly.
+    assert!(supervisor.next_restart(exit_status(0), true).is_some());
+  }
+
+  #[test]
+  fn always_restarts_after_clean_exits() {
+    let mut supervisor = Supervisor::new(policy(RestartMode::Always));
+    assert!(supervisor.next_restart(exit_status(0), false).is_some());
+    assert!(supervisor.next_restart(exit_status(1), false).is_some());
+  }
+
+  #[test]
+  fn gives_up_after_max_restarts() {
+    let mut supervisor = Supervisor::new(policy(RestartMode::Always));
+    for _ in 0..3 {
+      assert!(supervisor.next_restart(exit_status(1), false).is_some());
+    }
+    assert_eq!(supervisor.next_restart(exit_status(1), false), None);
+  }
+
+  #[test]
+  fn forgets_restarts_outside_window() {
+    let mut supervisor = Supervisor::new(RestartPolicy {
+      window: Duration::ZERO,
+      ..policy(RestartMode::Always)
+    });
+    for _ in 0..10 {
+      assert_eq!(
+        supervisor.next_restart(exit_status(1), false),
+        Some(Duration::from_secs(5))
+      );
+    }
+  }
+
+  #[test]
+  fn doubles_backoff_up_to_max() {
+    let mut supervisor = Supervisor::new(RestartPolicy {
+      max_restarts: 10,
+      ..policy(RestartMode::Always)
+    });
+    let delays: Vec<_> = (0..6)
+      .map(|_| supervisor.next_restart(exit_status(1), false).unwrap())
+      .map(|delay| delay.as_secs())
+      .collect();
+    assert_eq!(delays, [5, 10, 20, 40, 60, 60]);
+  }
+}
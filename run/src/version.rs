@@ -0,0 +1,183 @@
+use crate::integrity::{Artifact, Checksum};
+use reqwest::Client;
+use serde::Deserialize;
+
+pub const DEFAULT_MANIFEST_URL: &str =
+  "https://piston-meta.mojang.com/mc/game/version_manifest_v2.json";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VersionType {
+  Release,
+  Snapshot,
+  OldBeta,
+  OldAlpha,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct LatestVersions {
+  pub release: String,
+  pub snapshot: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ManifestEntry {
+  pub id: String,
+  #[serde(rename = "type")]
+  pub version_type: VersionType,
+  pub url: String,
+  pub time: String,
+  pub release_time: String,
+  /// Checksum of the version details, which only `version_manifest_v2.json`
+  /// lists
+  pub sha1: Option<String>,
+  /// Only listed in `version_manifest_v2.json`
+  pub compliance_level: Option<u32>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct VersionManifest {
+  pub latest: LatestVersions,
+  pub versions: Vec<ManifestEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VersionRequest {
+  Pinned(String),
+  LatestRelease,
+  LatestSnapshot,
+}
+
+impl VersionManifest {
+  pub fn find(&self, id: &str) -> Option<&ManifestEntry> {
+    self.versions.iter().find(|entry| entry.id == id)
+  }
+
+  pub fn select(&self, request: &VersionRequest) -> Option<&ManifestEntry> {
+    match request {
+      VersionRequest::Pinned(id) => self.find(id),
+      VersionRequest::LatestRelease => self.find(&self.latest.release),
+      VersionRequest::LatestSnapshot => self.find(&self.latest.snapshot),
+    }
+  }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Download {
+  pub url: String,
+  pub sha1: String,
+  pub size: u64,
+}
+
+impl From<&Download> for Artifact {
+  fn from(download: &Download) -> Self {
+    Self {
+      url: download.url.clone(),
+      size: Some(download.size),
+      checksum: Some(Checksum::Sha1(download.sha1.clone())),
+    }
+  }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct VersionDownloads {
+  pub server: Option<Download>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JavaVersion {
+  pub component: String,
+  pub major_version: u32,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VersionDetails {
+  pub id: String,
+  #[serde(rename = "type")]
+  pub version_type: VersionType,
+  pub release_time: String,
+  pub downloads: VersionDownloads,
+  pub java_version: Option<JavaVersion>,
+}
+
+pub struct VersionResolver {
+  client: Client,
+  manifest_url: String,
+}
+
+impl VersionResolver {
+  pub fn new(client: Client, manifest_url: impl Into<String>) -> Self {
+    Self {
+      client,
+      manifest_url: manifest_url.into(),
+    }
+  }
+
+  pub async fn manifest(&self) -> Result<VersionManifest, reqwest::Error> {
+    self
+      .client
+      .get(&self.manifest_url)
+      .send()
+      .await?
+      .error_for_status()?
+      .json()
+      .await
+  }
+
+  pub async fn details(&self, entry: &ManifestEntry) -> Result<VersionDetails, reqwest::Error> {
+    self
+      .client
+      .get(&entry.url)
+      .send()
+      .await?
+      .error_for_status()?
+      .json()
+      .await
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn reads_v1_manifest() {
+    let manifest: VersionManifest = serde_json::from_str(
+      r#"{
+        "latest": {"release": "1.20.1", "snapshot": "23w31a"},
+        "versions": [
+          {"id": "23w31a", "type": "snapshot", "url": "https://example.com/23w31a.json", "time": "2023-08-01T12:00:00+00:00", "releaseTime": "2023-08-01T11:00:00+00:00"},
+          {"id": "1.20.1", "type": "release", "url": "https://example.com/1.20.1.json", "time": "2023-06-12T12:00:00+00:00", "releaseTime": "2023-06-12T11:00:00+00:00"}
+        ]
+      }"#,
+    )
+    .unwrap();
+    let release = manifest.select(&VersionRequest::LatestRelease).unwrap();
+    assert_eq!(release.url, "https://example.com/1.20.1.json");
+    assert_eq!(release.sha1, None);
+    let snapshot = manifest.select(&VersionRequest::LatestSnapshot).unwrap();
+    assert_eq!(snapshot.id, "23w31a");
+  }
+
+  #[test]
+  fn reads_v2_manifest() {
+    let manifest: VersionManifest = serde_json::from_str(
+      r#"{
+        "latest": {"release": "1.20.1", "snapshot": "1.20.1"},
+        "versions": [
+          {"id": "1.20.1", "type": "release", "url": "https://example.com/1.20.1.json", "time": "2023-06-12T12:00:00+00:00", "releaseTime": "2023-06-12T11:00:00+00:00", "sha1": "0123456789abcdef0123456789abcdef01234567", "complianceLevel": 1}
+        ]
+      }"#,
+    )
+    .unwrap();
+    let entry = manifest.find("1.20.1").unwrap();
+    assert_eq!(
+      entry.sha1.as_deref(),
+      Some("0123456789abcdef0123456789abcdef01234567")
+    );
+    assert_eq!(entry.compliance_level, Some(1));
+  }
+}